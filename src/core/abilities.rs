@@ -0,0 +1,453 @@
+//! Special attacks that units use on their own, with a cooldown in between.
+
+use bevy::prelude::*;
+
+use super::{
+    attack,
+    level::Lane,
+    stats::{AttackArea, AttackStats, DamageType, Health, StatModifiers},
+    status_effects::{StatusEffect, StatusEffectKind},
+    targeting::{PlayerTargeting, TargetCandidate, TargetingPolicy},
+    Attack, CoreStep, Faction, Unit, UnitType,
+};
+
+/// How far in front of its target a leaping unit lands.
+const LEAP_DISTANCE: f32 = 15.;
+
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, tick_cooldowns.in_set(CoreStep::Prepare))
+            // Attacks made by abilities hit in the same frame
+            .add_systems(Update, cast_abilities.before(attack).in_set(CoreStep::Act))
+            .add_systems(Update, use_passive_abilities.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbilityEffect {
+    /// Attack the target with the stats of the ability.
+    Strike,
+    /// Jump right in front of the target, then strike it.
+    Leap,
+    /// Passive, restore the given health whenever the cooldown is over.
+    Regenerate(f32),
+}
+
+impl AbilityEffect {
+    /// Whether the ability takes effect on its own, without being cast at a target.
+    pub fn is_passive(&self) -> bool {
+        matches!(self, Self::Regenerate(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ability {
+    pub effect: AbilityEffect,
+    /// The attack made by the ability, its range is also the range in which the ability is used.
+    pub stats: AttackStats,
+    /// The minimum number of enemies in range to use the ability.
+    pub min_targets: usize,
+    pub cooldown: Timer,
+}
+
+impl Ability {
+    /// Create an ability that becomes ready after the first cooldown.
+    pub fn new(
+        effect: AbilityEffect,
+        stats: AttackStats,
+        min_targets: usize,
+        cooldown_secs: f32,
+    ) -> Self {
+        Self {
+            effect,
+            stats,
+            min_targets,
+            cooldown: Timer::from_seconds(cooldown_secs, TimerMode::Once),
+        }
+    }
+
+    /// Create a passive ability, that takes effect every time the cooldown is over.
+    pub fn passive(effect: AbilityEffect, cooldown_secs: f32) -> Self {
+        Self::new(effect, AttackStats::default(), 0, cooldown_secs)
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+pub struct Abilities(pub Vec<Ability>);
+
+impl Abilities {
+    pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+        match unit_type {
+            // A volley of arrows, hitting everything in a line
+            UnitType::Archer => Some(Self(vec![Ability::new(
+                AbilityEffect::Strike,
+                AttackStats {
+                    attack_range: 120.,
+                    attack_damage: 1.5,
+                    damage_type: DamageType::Pierce,
+                    area: AttackArea::Line { width: 15. },
+                    foreswing: 1.5,
+                    can_target_air: true,
+                    ..default()
+                },
+                3,
+                12.,
+            )])),
+            // A pitchfork charge, stunning an enemy that's not yet in reach
+            UnitType::Farmer => Some(Self(vec![Ability::new(
+                AbilityEffect::Leap,
+                AttackStats {
+                    attack_range: 60.,
+                    min_attack_range: 30.,
+                    attack_damage: 3.,
+                    status_effect: Some(StatusEffect {
+                        kind: StatusEffectKind::Stun,
+                        duration: 1.,
+                    }),
+                    foreswing: 0.5,
+                    ..default()
+                },
+                1,
+                10.,
+            )])),
+            // A blink past the front line
+            UnitType::Shadow => Some(Self(vec![Ability::new(
+                AbilityEffect::Leap,
+                AttackStats {
+                    attack_range: 90.,
+                    min_attack_range: 30.,
+                    attack_damage: 2.,
+                    damage_type: DamageType::Magic,
+                    foreswing: 0.3,
+                    ..default()
+                },
+                1,
+                8.,
+            )])),
+            UnitType::Hero => Some(Self(vec![
+                // A sweeping blow, hitting all enemies in front
+                Ability::new(
+                    AbilityEffect::Strike,
+                    AttackStats {
+                        attack_range: 35.,
+                        attack_damage: 4.,
+                        area: AttackArea::Cone { half_angle: 0.8 },
+                        foreswing: 0.4,
+                        ..default()
+                    },
+                    1,
+                    4.,
+                ),
+                // A leap into the enemy lines, stunning the target
+                Ability::new(
+                    AbilityEffect::Leap,
+                    AttackStats {
+                        attack_range: 80.,
+                        attack_damage: 5.,
+                        status_effect: Some(StatusEffect {
+                            kind: StatusEffectKind::Stun,
+                            duration: 1.5,
+                        }),
+                        foreswing: 0.3,
+                        ..default()
+                    },
+                    1,
+                    10.,
+                ),
+            ])),
+            // Stone that slowly mends itself
+            UnitType::Golem => Some(Self(vec![Ability::passive(
+                AbilityEffect::Regenerate(3.),
+                5.,
+            )])),
+            _ => None,
+        }
+    }
+
+    /// Start casting the first ready ability whose conditions are met.
+    ///
+    /// The ability is put on cooldown.
+    pub fn trigger(
+        &mut self,
+        origin: Vec3,
+        direction: f32,
+        policy: TargetingPolicy,
+        modifiers: &StatModifiers,
+        candidates: &[TargetCandidate],
+    ) -> Option<Casting> {
+        (0..self.0.len())
+            .find_map(|index| self.cast(index, origin, direction, policy, modifiers, candidates))
+    }
+
+    /// Start casting the ability with the given index, if it's ready and its conditions are met.
+    ///
+    /// The ability is put on cooldown.
+    pub fn cast(
+        &mut self,
+        index: usize,
+        origin: Vec3,
+        direction: f32,
+        policy: TargetingPolicy,
+        modifiers: &StatModifiers,
+        candidates: &[TargetCandidate],
+    ) -> Option<Casting> {
+        let ability = self.0.get_mut(index)?;
+
+        if ability.effect.is_passive() || !ability.cooldown.finished() {
+            return None;
+        }
+
+        let stats = ability.stats.with_modifiers(modifiers);
+        let range = stats.range();
+
+        let in_range: Vec<TargetCandidate> = candidates
+            .iter()
+            .copied()
+            .filter(|candidate| candidate.is_reachable_by(&stats))
+            .filter(|candidate| candidate.is_in_range(origin, direction, &range))
+            .collect();
+
+        if in_range.len() < ability.min_targets {
+            return None;
+        }
+
+        let target = policy.select(origin, direction, range, in_range)?;
+        ability.cooldown.reset();
+
+        Some(Casting::Foreswing {
+            ability: index,
+            target: target.entity,
+            timer: Timer::from_seconds(stats.foreswing_secs(), TimerMode::Once),
+        })
+    }
+}
+
+/// A unit using one of its abilities, instead of attacking.
+#[derive(Debug, Component)]
+pub enum Casting {
+    Foreswing {
+        /// The index of the ability in the unit's abilities.
+        ability: usize,
+        target: Entity,
+        timer: Timer,
+    },
+    Backswing(Timer),
+}
+
+fn tick_cooldowns(mut unit_query: Query<&mut Abilities>, time: Res<Time>) {
+    for mut abilities in unit_query.iter_mut() {
+        for ability in abilities.0.iter_mut() {
+            ability.cooldown.tick(time.delta());
+        }
+    }
+}
+
+fn cast_abilities(
+    mut commands: Commands,
+    mut attack_event: EventWriter<Attack>,
+    player_targeting: Res<PlayerTargeting>,
+    mut unit_query: Query<
+        (
+            Entity,
+            &mut Casting,
+            &mut Transform,
+            &Abilities,
+            &StatModifiers,
+            &TargetingPolicy,
+            Option<&Lane>,
+            &Faction,
+        ),
+        With<Unit>,
+    >,
+    target_query: Query<(&GlobalTransform, &Health)>,
+    time: Res<Time>,
+) {
+    for (entity, mut casting, mut transform, abilities, modifiers, policy, lane, faction) in
+        unit_query.iter_mut()
+    {
+        match &mut *casting {
+            Casting::Foreswing {
+                ability,
+                target,
+                timer,
+            } => {
+                let Some(ability) = abilities.0.get(*ability) else {
+                    commands.entity(entity).remove::<Casting>();
+                    continue;
+                };
+                let stats = ability.stats.with_modifiers(modifiers);
+
+                let Ok((target_transform, target_health)) = target_query.get(*target) else {
+                    commands.entity(entity).remove::<Casting>();
+                    continue;
+                };
+
+                if target_health.is_dead() {
+                    commands.entity(entity).remove::<Casting>();
+                    continue;
+                }
+
+                if !timer.tick(time.delta()).finished() {
+                    continue;
+                }
+
+                let direction =
+                    faction.facing(transform.translation.x, target_transform.translation().x);
+                let mut attack_stats = stats.clone();
+
+                if ability.effect == AbilityEffect::Leap {
+                    transform.translation.x =
+                        target_transform.translation().x - direction * LEAP_DISTANCE;
+                    // After leaping, the target is right in front of the unit
+                    attack_stats.min_attack_range = 0.;
+                }
+
+                attack_event.send(Attack {
+                    attacker: entity,
+                    faction: *faction,
+                    lane: lane.copied(),
+                    target: *target,
+                    stats: attack_stats,
+                    policy: player_targeting.policy_for(*policy, *faction),
+                    transform: *transform,
+                    direction: Vec3::new(direction, 0., 0.),
+                });
+
+                *casting = Casting::Backswing(Timer::from_seconds(
+                    stats.backswing_secs(),
+                    TimerMode::Once,
+                ));
+            }
+            Casting::Backswing(ref mut timer) => {
+                if timer.tick(time.delta()).finished() {
+                    commands.entity(entity).remove::<Casting>();
+                }
+            }
+        }
+    }
+}
+
+fn use_passive_abilities(mut unit_query: Query<(&mut Abilities, &mut Health)>) {
+    for (mut abilities, mut health) in unit_query.iter_mut() {
+        for ability in abilities.0.iter_mut() {
+            if !ability.effect.is_passive() || !ability.cooldown.finished() {
+                continue;
+            }
+
+            match ability.effect {
+                AbilityEffect::Regenerate(amount) => health.heal(amount),
+                AbilityEffect::Strike | AbilityEffect::Leap => {}
+            }
+
+            ability.cooldown.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::stats::MovementLayer;
+
+    use super::*;
+
+    fn enemy(index: u32, x: f32) -> TargetCandidate {
+        TargetCandidate {
+            entity: Entity::from_raw(index),
+            translation: Vec3::new(x, 0., 0.),
+            health: 10.,
+            threat: 1.,
+            is_base: false,
+            layer: MovementLayer::Ground,
+            is_concealed: false,
+        }
+    }
+
+    /// The abilities of the unit type, with their first cooldown already over.
+    fn ready_abilities(unit_type: UnitType) -> Abilities {
+        let mut abilities = Abilities::for_unit(unit_type).unwrap();
+
+        for ability in abilities.0.iter_mut() {
+            let cooldown = ability.cooldown.duration();
+            ability.cooldown.tick(cooldown);
+        }
+
+        abilities
+    }
+
+    fn trigger(abilities: &mut Abilities, candidates: &[TargetCandidate]) -> Option<Casting> {
+        abilities.trigger(
+            Vec3::ZERO,
+            1.,
+            TargetingPolicy::Closest,
+            &StatModifiers::default(),
+            candidates,
+        )
+    }
+
+    #[test]
+    fn abilities_wait_for_enough_targets_in_range() {
+        let mut abilities = ready_abilities(UnitType::Archer);
+
+        let too_few = [enemy(0, 50.), enemy(1, 60.), enemy(2, 500.)];
+        assert!(trigger(&mut abilities, &too_few).is_none());
+
+        let enough = [enemy(0, 50.), enemy(1, 60.), enemy(2, 70.)];
+        assert!(trigger(&mut abilities, &enough).is_some());
+    }
+
+    #[test]
+    fn abilities_go_on_cooldown_when_cast() {
+        let mut abilities = ready_abilities(UnitType::Farmer);
+        let candidates = [enemy(0, 40.)];
+
+        assert!(trigger(&mut abilities, &candidates).is_some());
+        assert!(trigger(&mut abilities, &candidates).is_none());
+    }
+
+    #[test]
+    fn trigger_falls_back_to_the_next_ready_ability() {
+        let mut abilities = ready_abilities(UnitType::Hero);
+        // Only the leap reaches that far
+        let candidates = [enemy(0, 70.)];
+
+        let casting = trigger(&mut abilities, &candidates);
+
+        assert!(matches!(
+            casting,
+            Some(Casting::Foreswing { ability: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn leaps_need_a_target_out_of_reach() {
+        let mut abilities = ready_abilities(UnitType::Farmer);
+
+        assert!(trigger(&mut abilities, &[enemy(0, 10.)]).is_none());
+    }
+
+    #[test]
+    fn passive_abilities_take_effect_on_their_own() {
+        let mut app = App::new();
+        app.add_systems(Update, use_passive_abilities);
+
+        let mut health = Health::from_max(30.);
+        health.apply_damage(10.);
+        let golem = app
+            .world_mut()
+            .spawn((ready_abilities(UnitType::Golem), health))
+            .id();
+
+        // Passive abilities are never cast
+        let mut abilities = ready_abilities(UnitType::Golem);
+        assert!(trigger(&mut abilities, &[enemy(0, 10.)]).is_none());
+
+        app.update();
+
+        let health = app.world().get::<Health>(golem).unwrap();
+        let abilities = app.world().get::<Abilities>(golem).unwrap();
+        assert_eq!(health.current(), 23.);
+        assert!(!abilities.0[0].cooldown.finished());
+    }
+}
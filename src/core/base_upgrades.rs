@@ -0,0 +1,167 @@
+//! Repairing and upgrading the player base with coins.
+
+use bevy::prelude::*;
+
+use std::fmt::Display;
+
+use super::{
+    inventory::Inventory,
+    stats::{DamageReduction, Health},
+    Base, CoreStep, Faction,
+};
+
+const REPAIR_COST: u32 = 15;
+const REPAIR_AMOUNT: f32 = 20.;
+
+/// The minimum time between two repairs, so the base can't be kept alive forever.
+const REPAIR_COOLDOWN_SECS: f32 = 15.;
+
+const MAX_HEALTH_LEVELS: u32 = 3;
+const MAX_HEALTH_PER_LEVEL: f32 = 25.;
+
+const ARMOR_LEVELS: u32 = 3;
+const DAMAGE_REDUCTION_PER_LEVEL: f32 = 0.15;
+
+pub struct BaseUpgradesPlugin;
+
+impl Plugin for BaseUpgradesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UpgradeBase>()
+            .add_systems(Update, tick_repair_cooldown.in_set(CoreStep::Prepare))
+            .add_systems(Update, upgrade_base.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseUpgrade {
+    /// Restore some health, limited by a cooldown.
+    Repair,
+    /// Raise the maximum health.
+    MaxHealth,
+    /// Reduce all damage taken.
+    Armor,
+}
+
+impl BaseUpgrade {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Repair, Self::MaxHealth, Self::Armor]
+    }
+}
+
+impl Display for BaseUpgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Repair => "Repair",
+            Self::MaxHealth => "Walls",
+            Self::Armor => "Armor",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Buy the upgrade for the player base, if it's affordable and available.
+#[derive(Debug, Event)]
+pub struct UpgradeBase(pub BaseUpgrade);
+
+/// The upgrades bought for the player base so far.
+#[derive(Debug, Resource)]
+pub struct BaseUpgrades {
+    pub max_health_level: u32,
+    pub armor_level: u32,
+    pub repair_cooldown: Timer,
+}
+
+impl Default for BaseUpgrades {
+    fn default() -> Self {
+        // The first repair is available right away
+        let mut repair_cooldown = Timer::from_seconds(REPAIR_COOLDOWN_SECS, TimerMode::Once);
+        repair_cooldown.tick(repair_cooldown.duration());
+
+        Self {
+            max_health_level: 0,
+            armor_level: 0,
+            repair_cooldown,
+        }
+    }
+}
+
+impl BaseUpgrades {
+    /// The cost of buying the upgrade now, `None` if it's not available.
+    pub fn cost(&self, upgrade: BaseUpgrade) -> Option<u32> {
+        match upgrade {
+            BaseUpgrade::Repair => self.repair_cooldown.finished().then_some(REPAIR_COST),
+            BaseUpgrade::MaxHealth => (self.max_health_level < MAX_HEALTH_LEVELS)
+                .then_some(30 * (self.max_health_level + 1)),
+            BaseUpgrade::Armor => {
+                (self.armor_level < ARMOR_LEVELS).then_some(40 * (self.armor_level + 1))
+            }
+        }
+    }
+}
+
+fn tick_repair_cooldown(mut base_upgrades: ResMut<BaseUpgrades>, time: Res<Time>) {
+    base_upgrades.repair_cooldown.tick(time.delta());
+}
+
+fn upgrade_base(
+    mut upgrade_base_event: EventReader<UpgradeBase>,
+    mut inventory: ResMut<Inventory>,
+    mut base_upgrades: ResMut<BaseUpgrades>,
+    mut base_query: Query<(&mut Health, &mut DamageReduction, &Faction), With<Base>>,
+) {
+    for UpgradeBase(upgrade) in upgrade_base_event.read() {
+        let Some((mut health, mut reduction, _)) = base_query
+            .iter_mut()
+            .find(|(_, _, faction)| **faction == Faction::Player)
+        else {
+            continue;
+        };
+
+        // Don't waste coins on repairing an intact base
+        if *upgrade == BaseUpgrade::Repair && !health.is_injured() {
+            continue;
+        }
+
+        let Some(cost) = base_upgrades.cost(*upgrade) else {
+            continue;
+        };
+
+        if !inventory.coins.try_remove(cost) {
+            continue;
+        }
+
+        match upgrade {
+            BaseUpgrade::Repair => {
+                health.heal(REPAIR_AMOUNT);
+                base_upgrades.repair_cooldown.reset();
+            }
+            BaseUpgrade::MaxHealth => {
+                health.increase_max(MAX_HEALTH_PER_LEVEL);
+                base_upgrades.max_health_level += 1;
+            }
+            BaseUpgrade::Armor => {
+                base_upgrades.armor_level += 1;
+                reduction.0 = base_upgrades.armor_level as f32 * DAMAGE_REDUCTION_PER_LEVEL;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_get_more_expensive_until_maxed() {
+        let mut base_upgrades = BaseUpgrades::default();
+
+        assert_eq!(base_upgrades.cost(BaseUpgrade::Armor), Some(40));
+
+        base_upgrades.armor_level = 1;
+        assert_eq!(base_upgrades.cost(BaseUpgrade::Armor), Some(80));
+
+        base_upgrades.armor_level = ARMOR_LEVELS;
+        assert_eq!(base_upgrades.cost(BaseUpgrade::Armor), None);
+    }
+}
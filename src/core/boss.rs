@@ -0,0 +1,147 @@
+//! Boss units with large health pools that change their behavior in phases.
+
+use bevy::prelude::*;
+
+use super::{
+    level::Lane,
+    stats::{AttackArea, AttackStats, DamageType, Health, StatModifiers},
+    status_effects::{StatusEffect, StatusEffectKind},
+    CoreStep, Faction, SpawnUnit, UnitType,
+};
+
+/// The number of bosses that have to be defeated to win the game.
+pub const BOSSES_TO_WIN: u32 = 2;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_boss_phase.in_set(CoreStep::Modifiers))
+            .add_systems(Update, advance_boss_phases.in_set(CoreStep::Effects));
+    }
+}
+
+/// The number of bosses defeated by the player.
+#[derive(Debug, Resource, Default)]
+pub struct BossesDefeated(pub u32);
+
+/// A part of a boss fight, starting when the boss health drops to the threshold.
+#[derive(Debug, Clone)]
+pub struct BossPhase {
+    /// The fraction of the maximum health at which the phase starts.
+    pub health_threshold: f32,
+    /// The factor applied to the movement speed during the phase.
+    pub speed: f32,
+    /// The attack used from the start of the phase on.
+    pub attack_stats: Option<AttackStats>,
+    /// The minions summoned at the start of the phase.
+    pub summon: Option<(UnitType, u32)>,
+}
+
+#[derive(Debug, Component, Clone)]
+pub struct Boss {
+    /// The phases after the initial one, ordered by decreasing health threshold.
+    pub phases: Vec<BossPhase>,
+    /// The number of phases that have started so far.
+    pub current: usize,
+}
+
+impl Boss {
+    pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+        match unit_type {
+            UnitType::NightLord => Some(Self {
+                phases: vec![
+                    BossPhase {
+                        health_threshold: 0.66,
+                        speed: 1.,
+                        attack_stats: Some(AttackStats {
+                            status_effect: Some(StatusEffect {
+                                kind: StatusEffectKind::Burn(1.),
+                                duration: 3.,
+                            }),
+                            ..AttackStats::from(UnitType::NightLord)
+                        }),
+                        summon: Some((UnitType::Shadow, 3)),
+                    },
+                    BossPhase {
+                        health_threshold: 0.33,
+                        speed: 1.8,
+                        attack_stats: Some(AttackStats {
+                            attack_range: 60.,
+                            damage_type: DamageType::Magic,
+                            area: AttackArea::Line { width: 20. },
+                            status_effect: Some(StatusEffect {
+                                kind: StatusEffectKind::Stun,
+                                duration: 0.5,
+                            }),
+                            foreswing: 1.,
+                            ..AttackStats::from(UnitType::NightLord)
+                        }),
+                        summon: Some((UnitType::Wraith, 2)),
+                    },
+                ],
+                current: 0,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The phase that is currently active, if the boss left its initial phase.
+    pub fn phase(&self) -> Option<&BossPhase> {
+        self.current
+            .checked_sub(1)
+            .and_then(|index| self.phases.get(index))
+    }
+}
+
+fn apply_boss_phase(mut boss_query: Query<(&Boss, &mut StatModifiers)>) {
+    for (boss, mut modifiers) in boss_query.iter_mut() {
+        if let Some(phase) = boss.phase() {
+            modifiers.speed *= phase.speed;
+        }
+    }
+}
+
+fn advance_boss_phases(
+    mut spawn_unit_event: EventWriter<SpawnUnit>,
+    mut boss_query: Query<(
+        &mut Boss,
+        &mut AttackStats,
+        &Health,
+        &Transform,
+        Option<&Lane>,
+        &Faction,
+    )>,
+) {
+    for (mut boss, mut attack_stats, health, transform, lane, faction) in boss_query.iter_mut() {
+        // A boss killed by a single blow doesn't go through its last phases
+        if health.is_dead() {
+            continue;
+        }
+
+        let health_fraction = health.current() / health.max();
+
+        while let Some(phase) = boss.phases.get(boss.current).cloned() {
+            if health_fraction > phase.health_threshold {
+                break;
+            }
+
+            boss.current += 1;
+
+            if let Some(new_attack_stats) = phase.attack_stats {
+                *attack_stats = new_attack_stats;
+            }
+
+            if let Some((unit_type, count)) = phase.summon {
+                for _ in 0..count {
+                    spawn_unit_event.send(SpawnUnit {
+                        faction: *faction,
+                        unit_type,
+                        x: Some(transform.translation.x),
+                        lane: lane.map(|lane| lane.0),
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Neutral points on the battlefield that are captured by holding them with units.
+
+use bevy::prelude::*;
+
+use std::fmt::Display;
+
+use super::{inventory::Inventory, level::Lane, stats::StatModifiers, CoreStep, Faction, Unit};
+
+/// How close to a capture point units have to be to contest it.
+pub const CAPTURE_RADIUS: f32 = 30.;
+
+/// How long it takes to capture a neutral point with no one contesting it.
+const CAPTURE_SECS: f32 = 5.;
+
+/// The extra coins per second for each income point held by the player.
+const INCOME_PER_SECOND: f32 = 1.;
+
+/// The factor applied to the attack damage of all units of the side holding a war banner.
+const WAR_BANNER_DAMAGE: f32 = 1.15;
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, capture_income.in_set(CoreStep::Prepare))
+            .add_systems(Update, apply_war_banners.in_set(CoreStep::Modifiers))
+            .add_systems(Update, contest_capture_points.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureReward {
+    /// Extra coins for the player.
+    Income,
+    /// More attack damage for all units of the side holding it.
+    WarBanner,
+}
+
+impl Display for CaptureReward {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Income => "Income",
+            Self::WarBanner => "War Banner",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Component, Clone)]
+pub struct CapturePoint {
+    pub reward: CaptureReward,
+    /// How far the point is captured, from -1 for the foes to 1 for the player.
+    pub control: f32,
+    /// The side holding the point, `None` while no one does.
+    pub owner: Option<Faction>,
+}
+
+impl CapturePoint {
+    pub fn new(reward: CaptureReward) -> Self {
+        Self {
+            reward,
+            control: 0.,
+            owner: None,
+        }
+    }
+
+    /// Shift the control towards the side that is alone at the point.
+    pub fn contest(&mut self, player_units: usize, foe_units: usize, delta_secs: f32) {
+        let pull = match (player_units > 0, foe_units > 0) {
+            (true, false) => 1.,
+            (false, true) => -1.,
+            // Nothing changes while both sides or no one is there
+            _ => return,
+        };
+
+        self.control = (self.control + pull * delta_secs / CAPTURE_SECS).clamp(-1., 1.);
+
+        if self.control >= 1. {
+            self.owner = Some(Faction::Player);
+        } else if self.control <= -1. {
+            self.owner = Some(Faction::Foe);
+        } else if (self.owner == Some(Faction::Player) && self.control <= 0.)
+            || (self.owner == Some(Faction::Foe) && self.control >= 0.)
+        {
+            // The owner loses the point once it's pulled past the middle
+            self.owner = None;
+        }
+    }
+}
+
+/// The most forward point held by the side, where its new units spawn.
+pub fn spawn_point<'a>(
+    faction: Faction,
+    points: impl IntoIterator<Item = (f32, &'a CapturePoint)>,
+) -> Option<f32> {
+    points
+        .into_iter()
+        .filter(|(_, point)| point.owner == Some(faction))
+        .map(|(x, _)| x)
+        .max_by(|a, b| (a * faction.direction()).total_cmp(&(b * faction.direction())))
+}
+
+fn contest_capture_points(
+    mut point_query: Query<(&Transform, &mut CapturePoint, Option<&Lane>)>,
+    unit_query: Query<(&Transform, &Faction, Option<&Lane>), With<Unit>>,
+    time: Res<Time>,
+) {
+    for (point_transform, mut point, point_lane) in point_query.iter_mut() {
+        // Only the units in the lane of the point contest it
+        let (player_units, foe_units) = unit_query
+            .iter()
+            .filter(|(transform, _, lane)| {
+                (transform.translation.x - point_transform.translation.x).abs() <= CAPTURE_RADIUS
+                    && Lane::meets(point_lane, *lane)
+            })
+            .fold(
+                (0, 0),
+                |(player_units, foe_units), (_, faction, _)| match faction {
+                    Faction::Player => (player_units + 1, foe_units),
+                    Faction::Foe => (player_units, foe_units + 1),
+                    // Neutral units don't take part in capturing
+                    Faction::Neutral => (player_units, foe_units),
+                },
+            );
+
+        point.contest(player_units, foe_units, time.delta_seconds());
+    }
+}
+
+fn capture_income(
+    mut inventory: ResMut<Inventory>,
+    point_query: Query<&CapturePoint>,
+    time: Res<Time>,
+) {
+    let income_points = point_query
+        .iter()
+        .filter(|point| {
+            point.reward == CaptureReward::Income && point.owner == Some(Faction::Player)
+        })
+        .count();
+
+    inventory
+        .coins
+        .add_until_full(income_points as f32 * INCOME_PER_SECOND * time.delta_seconds());
+}
+
+fn apply_war_banners(
+    point_query: Query<&CapturePoint>,
+    mut unit_query: Query<(&mut StatModifiers, &Faction), With<Unit>>,
+) {
+    for point in point_query.iter() {
+        let Some(owner) = point.owner else {
+            continue;
+        };
+
+        if point.reward != CaptureReward::WarBanner {
+            continue;
+        }
+
+        for (mut modifiers, faction) in unit_query.iter_mut() {
+            if *faction == owner {
+                modifiers.attack_damage *= WAR_BANNER_DAMAGE;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn points_are_captured_and_lost_past_the_middle() {
+        let mut point = CapturePoint::new(CaptureReward::Income);
+
+        point.contest(2, 0, CAPTURE_SECS);
+        assert_eq!(point.owner, Some(Faction::Player));
+
+        // Contested points don't change hands
+        point.contest(1, 1, CAPTURE_SECS);
+        assert_eq!(point.owner, Some(Faction::Player));
+
+        point.contest(0, 1, CAPTURE_SECS * 0.6);
+        assert_eq!(point.owner, Some(Faction::Player));
+
+        point.contest(0, 1, CAPTURE_SECS * 0.6);
+        assert_eq!(point.owner, None);
+
+        point.contest(0, 1, CAPTURE_SECS);
+        assert_eq!(point.owner, Some(Faction::Foe));
+    }
+
+    #[test]
+    fn only_units_in_the_lane_of_a_point_contest_it() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_systems(Update, contest_capture_points);
+        let point = app
+            .world_mut()
+            .spawn((
+                CapturePoint::new(CaptureReward::Income),
+                Lane(0),
+                Transform::default(),
+            ))
+            .id();
+        app.world_mut()
+            .spawn((Unit, Faction::Player, Lane(0), Transform::default()));
+        app.world_mut()
+            .spawn((Unit, Faction::Foe, Lane(1), Transform::default()));
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(CAPTURE_SECS));
+        app.update();
+
+        let point = app.world().get::<CapturePoint>(point).unwrap();
+        assert_eq!(point.owner, Some(Faction::Player));
+    }
+}
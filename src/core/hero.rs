@@ -0,0 +1,323 @@
+//! The hero, a unique unit that the player controls directly.
+
+use bevy::prelude::*;
+
+use super::{
+    abilities::{Abilities, Casting},
+    level::Lane,
+    light::Concealed,
+    stats::{AttackStats, Health, MovementLayer, MovementStats, StatModifiers},
+    status_effects::Stunned,
+    targeting::{PlayerTargeting, TargetCandidate, TargetingPolicy},
+    Attacking, Base, CoreStep, Faction, SpawnUnit, Unit, UnitType,
+};
+
+/// The time it takes the hero to come back after it died.
+const HERO_RESPAWN_SECS: f32 = 20.;
+
+pub struct HeroPlugin;
+
+impl Plugin for HeroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HeroCommand>()
+            .add_systems(Update, respawn_hero.in_set(CoreStep::Prepare))
+            .add_systems(Update, control_hero.in_set(CoreStep::Behavior));
+    }
+}
+
+/// Marks the hero, which ignores the automatic unit behavior.
+#[derive(Debug, Component, Default)]
+pub struct Hero {
+    /// An ability that was used while the hero couldn't act, cast as soon as it can.
+    queued_ability: Option<usize>,
+}
+
+/// The time until the hero (re)spawns, ticking only while it's not on the battlefield.
+#[derive(Debug, Resource)]
+pub struct HeroRespawn(pub Timer);
+
+impl Default for HeroRespawn {
+    fn default() -> Self {
+        // The hero joins the battle right away
+        Self(Timer::from_seconds(0., TimerMode::Once))
+    }
+}
+
+#[derive(Debug, Event, Clone, Copy)]
+pub enum HeroCommand {
+    /// Walk towards the given direction along the battlefield.
+    Move(f32),
+    /// Use the ability with the given index.
+    UseAbility(usize),
+}
+
+fn respawn_hero(
+    mut hero_respawn: ResMut<HeroRespawn>,
+    mut spawn_unit_event: EventWriter<SpawnUnit>,
+    hero_query: Query<(), With<Hero>>,
+    time: Res<Time>,
+) {
+    if !hero_query.is_empty() {
+        return;
+    }
+
+    if hero_respawn.0.tick(time.delta()).finished() {
+        spawn_unit_event.send(SpawnUnit {
+            faction: Faction::Player,
+            unit_type: UnitType::Hero,
+            x: None,
+            lane: None,
+        });
+
+        hero_respawn.0 = Timer::from_seconds(HERO_RESPAWN_SECS, TimerMode::Once);
+    }
+}
+
+fn control_hero(
+    mut commands: Commands,
+    mut hero_command: EventReader<HeroCommand>,
+    player_targeting: Res<PlayerTargeting>,
+    mut hero_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &MovementStats,
+            &AttackStats,
+            &StatModifiers,
+            &TargetingPolicy,
+            &mut Abilities,
+            &mut Hero,
+            Option<&Lane>,
+            &Faction,
+            Has<Attacking>,
+            Has<Casting>,
+            Has<Stunned>,
+        ),
+        With<Hero>,
+    >,
+    other_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Health,
+            Option<&AttackStats>,
+            Option<&MovementLayer>,
+            Option<&Lane>,
+            &Faction,
+            Has<Base>,
+            Has<Concealed>,
+        ),
+        (Or<(With<Unit>, With<Base>)>, Without<Hero>),
+    >,
+    base_query: Query<&Transform, (With<Base>, Without<Hero>)>,
+    time: Res<Time>,
+) {
+    let commands_this_frame: Vec<HeroCommand> = hero_command.read().copied().collect();
+
+    let Ok((
+        entity,
+        mut transform,
+        movement_stats,
+        attack_stats,
+        modifiers,
+        policy,
+        mut abilities,
+        mut hero,
+        lane,
+        faction,
+        is_attacking,
+        is_casting,
+        is_stunned,
+    )) = hero_query.get_single_mut()
+    else {
+        return;
+    };
+
+    // Remember the latest ability press until the hero is able to use it
+    if let Some(index) = commands_this_frame
+        .iter()
+        .rev()
+        .find_map(|command| match command {
+            HeroCommand::UseAbility(index) => Some(*index),
+            HeroCommand::Move(_) => None,
+        })
+    {
+        hero.queued_ability = Some(index);
+    }
+
+    if is_casting || is_stunned {
+        return;
+    }
+
+    let direction = faction.direction();
+    let policy = player_targeting.policy_for(*policy, *faction);
+
+    let candidates: Vec<TargetCandidate> = other_query
+        .iter()
+        // Only fight enemies in the same lane
+        .filter(|(_, _, _, _, _, other_lane, other_faction, _, _)| {
+            faction.is_hostile_to(**other_faction) && Lane::meets(lane, *other_lane)
+        })
+        .map(
+            |(other, other_transform, health, other_stats, layer, _, _, is_base, is_concealed)| {
+                TargetCandidate::new(
+                    other,
+                    other_transform,
+                    health,
+                    other_stats,
+                    layer,
+                    is_base,
+                    is_concealed,
+                )
+            },
+        )
+        .collect();
+
+    if let Some(index) = hero.queued_ability.take() {
+        let casting = abilities.cast(
+            index,
+            transform.translation,
+            direction,
+            policy,
+            modifiers,
+            &candidates,
+        );
+
+        // Using an ability interrupts a normal attack
+        if let Some(casting) = casting {
+            commands
+                .entity(entity)
+                .remove::<Attacking>()
+                .insert(casting);
+            return;
+        }
+    }
+
+    if is_attacking {
+        return;
+    }
+
+    let mut has_orders = false;
+
+    for command in commands_this_frame {
+        if let HeroCommand::Move(move_direction) = command {
+            transform.translation.x +=
+                move_direction * movement_stats.speed * modifiers.speed * time.delta_seconds();
+            has_orders = true;
+
+            // Don't walk past the bases
+            let (min_x, max_x) = base_query.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(min_x, max_x), base| {
+                    (min_x.min(base.translation.x), max_x.max(base.translation.x))
+                },
+            );
+
+            if min_x <= max_x {
+                transform.translation.x = transform.translation.x.clamp(min_x, max_x);
+            }
+        }
+    }
+
+    // Without orders, the hero fights whoever is in range
+    if !has_orders {
+        let stats = attack_stats.with_modifiers(modifiers);
+
+        let target = policy.select(
+            transform.translation,
+            direction,
+            stats.range(),
+            candidates
+                .into_iter()
+                .filter(|candidate| candidate.is_reachable_by(&stats)),
+        );
+
+        if let Some(target) = target {
+            commands.entity(entity).insert(Attacking::Start {
+                target: target.entity,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An app running the hero control, with the hero at the origin and an enemy right in front.
+    fn setup() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<HeroCommand>()
+            .init_resource::<PlayerTargeting>()
+            .init_resource::<Time>()
+            .add_systems(Update, control_hero);
+
+        let mut abilities = Abilities::for_unit(UnitType::Hero).unwrap();
+        for ability in abilities.0.iter_mut() {
+            let cooldown = ability.cooldown.duration();
+            ability.cooldown.tick(cooldown);
+        }
+
+        let hero = app
+            .world_mut()
+            .spawn((
+                Hero::default(),
+                Unit,
+                Faction::Player,
+                Transform::default(),
+                MovementStats::from(UnitType::Hero),
+                AttackStats::from(UnitType::Hero),
+                StatModifiers::default(),
+                TargetingPolicy::from(UnitType::Hero),
+                abilities,
+            ))
+            .id();
+        app.world_mut().spawn((
+            Unit,
+            Faction::Foe,
+            Transform::from_xyz(20., 0., 0.),
+            Health::from_max(10.),
+        ));
+
+        (app, hero)
+    }
+
+    #[test]
+    fn abilities_interrupt_attacks() {
+        let (mut app, hero) = setup();
+        let target = Entity::from_raw(0);
+        app.world_mut()
+            .entity_mut(hero)
+            .insert(Attacking::Start { target });
+
+        app.world_mut().send_event(HeroCommand::UseAbility(0));
+        app.update();
+
+        assert!(app.world().get::<Casting>(hero).is_some());
+        assert!(app.world().get::<Attacking>(hero).is_none());
+    }
+
+    #[test]
+    fn abilities_used_while_casting_are_cast_afterwards() {
+        let (mut app, hero) = setup();
+        app.world_mut()
+            .entity_mut(hero)
+            .insert(Casting::Backswing(Timer::from_seconds(1., TimerMode::Once)));
+
+        app.world_mut().send_event(HeroCommand::UseAbility(0));
+        app.update();
+        assert_eq!(
+            app.world().get::<Hero>(hero).unwrap().queued_ability,
+            Some(0)
+        );
+
+        app.world_mut().entity_mut(hero).remove::<Casting>();
+        app.update();
+
+        assert!(matches!(
+            app.world().get::<Casting>(hero),
+            Some(Casting::Foreswing { ability: 0, .. })
+        ));
+        assert_eq!(app.world().get::<Hero>(hero).unwrap().queued_ability, None);
+    }
+}
@@ -5,6 +5,8 @@ use std::fmt::Display;
 #[derive(Debug, Resource)]
 pub struct Inventory {
     pub coins: Item,
+    /// Spent on the commander spells.
+    pub mana: Item,
 }
 
 #[derive(Debug)]
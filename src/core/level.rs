@@ -0,0 +1,248 @@
+//! The layout of the battlefield between the two bases.
+
+use bevy::prelude::*;
+
+use super::{
+    capture::CaptureReward,
+    light::TimeOfDay,
+    neutral::CreepCamp,
+    stats::{AttackStats, MovementLayer, StatModifiers},
+    weather::Weather,
+    CoreStep, Unit, UnitType,
+};
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_terrain.in_set(CoreStep::Modifiers));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    /// Slows ground units, but extends the range of ranged units on top.
+    Hill,
+    /// Slows ground units wading through it.
+    River,
+    /// A narrow crossing, ground units can only pass in single file.
+    Bridge,
+}
+
+impl Terrain {
+    /// The factor applied to the movement speed of ground units.
+    pub fn speed(&self) -> f32 {
+        match *self {
+            Self::Hill => 0.8,
+            Self::River => 0.5,
+            Self::Bridge => 1.,
+        }
+    }
+
+    /// The factor applied to the attack range of ranged units.
+    pub fn ranged_attack_range(&self) -> f32 {
+        match *self {
+            Self::Hill => 1.25,
+            Self::River | Self::Bridge => 1.,
+        }
+    }
+
+    /// The factor applied to the distance that ground units keep between each other.
+    pub fn spacing(&self) -> f32 {
+        match *self {
+            Self::Bridge => 2.,
+            Self::Hill | Self::River => 1.,
+        }
+    }
+}
+
+/// The lane a unit fights in, it only meets units in the same lane.
+///
+/// Entities without a lane, like the bases, can be reached from every lane.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub struct Lane(pub usize);
+
+impl Lane {
+    /// Whether entities in the two lanes can reach each other.
+    pub fn meets(a: Option<&Lane>, b: Option<&Lane>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+}
+
+/// A stretch of the battlefield with the same terrain.
+#[derive(Debug, Clone, Copy)]
+pub struct TerrainSegment {
+    pub terrain: Terrain,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl TerrainSegment {
+    pub fn new(terrain: Terrain, start: f32, end: f32) -> Self {
+        Self {
+            terrain,
+            start,
+            end,
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct Level {
+    /// The vertical position of each lane, from top to bottom.
+    pub lanes: Vec<f32>,
+    /// The terrain segments, everything else is flat ground.
+    ///
+    /// Where segments overlap the first one lies on top, like a bridge over a river.
+    pub terrain: Vec<TerrainSegment>,
+    /// The position and lane of each capture point, and what it gives to the side holding it.
+    pub capture_points: Vec<(f32, usize, CaptureReward)>,
+    /// The position of each camp of neutral monsters.
+    pub creep_camps: Vec<(f32, CreepCamp)>,
+    /// The weather during the battle, chosen at random if not set.
+    pub weather: Option<Weather>,
+    /// The time of day during the battle, chosen at random if not set.
+    pub time_of_day: Option<TimeOfDay>,
+}
+
+impl Level {
+    /// The lane that units spawn in if none was picked.
+    pub fn default_lane(&self) -> usize {
+        self.lanes.len() / 2
+    }
+
+    /// The lane closest to the given vertical position.
+    pub fn nearest_lane(&self, y: f32) -> usize {
+        self.lanes
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - y).abs().total_cmp(&(*b - y).abs()))
+            .map_or(0, |(index, _)| index)
+    }
+
+    /// The terrain at the given position, `None` for flat ground.
+    pub fn terrain_at(&self, x: f32) -> Option<Terrain> {
+        self.terrain
+            .iter()
+            .find(|segment| (segment.start..=segment.end).contains(&x))
+            .map(|segment| segment.terrain)
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            lanes: vec![60., 0., -60.],
+            terrain: vec![
+                TerrainSegment::new(Terrain::Hill, -130., -90.),
+                TerrainSegment::new(Terrain::Bridge, 45., 65.),
+                TerrainSegment::new(Terrain::River, 40., 70.),
+                TerrainSegment::new(Terrain::Hill, 110., 140.),
+            ],
+            capture_points: vec![
+                (-50., 0, CaptureReward::Income),
+                (50., 2, CaptureReward::WarBanner),
+            ],
+            creep_camps: vec![
+                (0., CreepCamp::new(UnitType::Wolf, 3, 0)),
+                (100., CreepCamp::new(UnitType::Troll, 1, 2)),
+            ],
+            weather: None,
+            time_of_day: None,
+        }
+    }
+}
+
+fn apply_terrain(
+    level: Res<Level>,
+    mut unit_query: Query<
+        (
+            &Transform,
+            &AttackStats,
+            &mut StatModifiers,
+            Option<&MovementLayer>,
+        ),
+        With<Unit>,
+    >,
+) {
+    for (transform, attack_stats, mut modifiers, layer) in unit_query.iter_mut() {
+        // Flying units are not affected by the ground below
+        if layer == Some(&MovementLayer::Air) {
+            continue;
+        }
+
+        let Some(terrain) = level.terrain_at(transform.translation.x) else {
+            continue;
+        };
+
+        modifiers.speed *= terrain.speed();
+
+        if attack_stats.is_ranged() {
+            modifiers.attack_range *= terrain.ranged_attack_range();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terrain_at_finds_the_segment() {
+        let level = Level {
+            lanes: vec![0.],
+            terrain: vec![TerrainSegment::new(Terrain::River, 10., 20.)],
+            capture_points: vec![],
+            creep_camps: vec![],
+            weather: None,
+            time_of_day: None,
+        };
+
+        assert_eq!(level.terrain_at(15.), Some(Terrain::River));
+        assert_eq!(level.terrain_at(25.), None);
+    }
+
+    #[test]
+    fn units_cross_the_river_over_the_bridge() {
+        let level = Level::default();
+        let segment = |terrain| {
+            level
+                .terrain
+                .iter()
+                .find(|segment| segment.terrain == terrain)
+                .unwrap()
+        };
+        let river = segment(Terrain::River);
+        let bridge = segment(Terrain::Bridge);
+
+        // The bridge doesn't stand on dry land
+        assert!(river.start <= bridge.start && bridge.end <= river.end);
+
+        let crossing: Vec<_> = (0..=(river.end - river.start) as usize)
+            .map(|step| level.terrain_at(river.start + step as f32))
+            .collect();
+
+        assert!(crossing
+            .iter()
+            .all(|terrain| matches!(terrain, Some(Terrain::River | Terrain::Bridge))));
+        assert_eq!(
+            level.terrain_at((bridge.start + bridge.end) / 2.),
+            Some(Terrain::Bridge)
+        );
+    }
+
+    #[test]
+    fn nearest_lane_picks_the_closest() {
+        let level = Level {
+            lanes: vec![60., 0., -60.],
+            ..default()
+        };
+
+        assert_eq!(level.nearest_lane(45.), 0);
+        assert_eq!(level.nearest_lane(-20.), 1);
+        assert_eq!(level.nearest_lane(-500.), 2);
+    }
+}
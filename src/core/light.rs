@@ -0,0 +1,268 @@
+//! Darkness hiding enemy units, and the light sources revealing them.
+
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+
+use std::fmt::Display;
+
+use super::{inventory::Inventory, level::Lane, weather::Weather, CoreStep, Faction, Unit};
+
+/// How far a player unit lights up the battlefield around it.
+const UNIT_LIGHT_RADIUS: f32 = 60.;
+
+/// How far the player base lights up the battlefield around it.
+const BASE_LIGHT_RADIUS: f32 = 100.;
+
+/// How far a torch lights up the battlefield around it.
+const TORCH_LIGHT_RADIUS: f32 = 80.;
+
+pub const TORCH_COST: u32 = 25;
+
+/// The maximum number of torches the player can build.
+const MAX_TORCHES: usize = 5;
+
+pub struct LightPlugin;
+
+impl Plugin for LightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BuildTorch>()
+            .add_systems(Update, update_concealment.in_set(CoreStep::Modifiers))
+            .add_systems(Update, build_torch.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Resource, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeOfDay {
+    #[default]
+    Day,
+    /// Only the surroundings of light sources can be seen.
+    Night,
+}
+
+impl TimeOfDay {
+    /// Choose a time of day at random.
+    pub fn random(global_rng: &mut GlobalRng) -> Self {
+        if global_rng.bool() {
+            Self::Day
+        } else {
+            Self::Night
+        }
+    }
+
+    /// The factor applied to the radius of light sources.
+    ///
+    /// By day everything is visible, unless the weather limits it.
+    fn light_factor(&self) -> f32 {
+        match *self {
+            Self::Day => f32::INFINITY,
+            Self::Night => 1.,
+        }
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Day => "Day",
+            Self::Night => "Night",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Reveals enemy units around the player units, base or torch carrying it.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct LightSource {
+    pub radius: f32,
+}
+
+impl LightSource {
+    pub fn unit() -> Self {
+        Self {
+            radius: UNIT_LIGHT_RADIUS,
+        }
+    }
+
+    pub fn base() -> Self {
+        Self {
+            radius: BASE_LIGHT_RADIUS,
+        }
+    }
+
+    pub fn torch() -> Self {
+        Self {
+            radius: TORCH_LIGHT_RADIUS,
+        }
+    }
+
+    /// How far the light reaches at the given time of day and weather.
+    pub fn reach(&self, time_of_day: TimeOfDay, weather: Weather) -> f32 {
+        (self.radius * time_of_day.light_factor()).min(weather.visibility())
+    }
+}
+
+/// A torch built by the player.
+#[derive(Debug, Component)]
+pub struct Torch;
+
+/// An enemy or neutral unit hidden in the dark or the fog.
+///
+/// It's not drawn and ranged units can't target it.
+#[derive(Debug, Component)]
+pub struct Concealed;
+
+/// Build a torch at the given position, if the player can afford it and hasn't built too many.
+#[derive(Debug, Event)]
+pub struct BuildTorch {
+    pub x: f32,
+}
+
+/// Whether any of the lights, given as position and radius, reaches the position.
+pub fn is_lit(x: f32, lights: impl IntoIterator<Item = (f32, f32)>) -> bool {
+    lights
+        .into_iter()
+        .any(|(light_x, radius)| (x - light_x).abs() <= radius)
+}
+
+fn update_concealment(
+    mut commands: Commands,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
+    light_query: Query<(&Transform, &LightSource, Option<&Lane>)>,
+    foe_query: Query<(Entity, &Transform, Option<&Lane>, &Faction, Has<Concealed>), With<Unit>>,
+) {
+    let lights: Vec<_> = light_query
+        .iter()
+        .map(|(transform, light, lane)| {
+            (
+                transform.translation.x,
+                light.reach(*time_of_day, *weather),
+                lane.copied(),
+            )
+        })
+        .collect();
+
+    for (foe, transform, lane, faction, is_concealed) in foe_query.iter() {
+        // The player always sees their own units
+        if *faction == Faction::Player {
+            continue;
+        }
+
+        // Units only light up their own lane, the base and torches all of them
+        let lane_lights = lights
+            .iter()
+            .filter(|(_, _, light_lane)| Lane::meets(lane, light_lane.as_ref()))
+            .map(|(x, radius, _)| (*x, *radius));
+        let is_lit = is_lit(transform.translation.x, lane_lights);
+
+        if is_lit && is_concealed {
+            commands.entity(foe).remove::<Concealed>();
+        } else if !is_lit && !is_concealed {
+            commands.entity(foe).try_insert(Concealed);
+        }
+    }
+}
+
+fn build_torch(
+    mut commands: Commands,
+    mut build_torch_event: EventReader<BuildTorch>,
+    mut inventory: ResMut<Inventory>,
+    torch_query: Query<(), With<Torch>>,
+) {
+    let mut torches = torch_query.iter().count();
+
+    for BuildTorch { x } in build_torch_event.read() {
+        if torches >= MAX_TORCHES || !inventory.coins.try_remove(TORCH_COST) {
+            continue;
+        }
+
+        torches += 1;
+
+        commands.spawn((
+            Torch,
+            LightSource::torch(),
+            TransformBundle {
+                local: Transform::from_xyz(*x, 0., -5.),
+                ..default()
+            },
+            VisibilityBundle::default(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{inventory::Item, weather::FOG_VISIBILITY};
+
+    use super::*;
+
+    #[test]
+    fn only_positions_within_a_radius_are_lit() {
+        let lights = [(0., 10.), (50., 5.)];
+
+        assert!(is_lit(8., lights));
+        assert!(is_lit(54., lights));
+        assert!(!is_lit(30., lights));
+    }
+
+    #[test]
+    fn fog_limits_how_far_lights_reach() {
+        let base = LightSource::base();
+
+        assert_eq!(
+            base.reach(TimeOfDay::Night, Weather::Clear),
+            BASE_LIGHT_RADIUS
+        );
+        assert_eq!(base.reach(TimeOfDay::Day, Weather::Clear), f32::INFINITY);
+        assert_eq!(base.reach(TimeOfDay::Day, Weather::Fog), FOG_VISIBILITY);
+    }
+
+    #[test]
+    fn nothing_is_concealed_during_a_clear_day() {
+        let mut app = App::new();
+        app.insert_resource(TimeOfDay::Day)
+            .insert_resource(Weather::Clear)
+            .add_systems(Update, update_concealment);
+        app.world_mut().spawn((
+            LightSource::base(),
+            TransformBundle::from_transform(Transform::from_xyz(-200., 0., 0.)),
+        ));
+        let foe = app
+            .world_mut()
+            .spawn((
+                Unit,
+                Faction::Foe,
+                Concealed,
+                TransformBundle::from_transform(Transform::from_xyz(5000., 0., 0.)),
+            ))
+            .id();
+
+        app.update();
+
+        assert!(app.world().get::<Concealed>(foe).is_none());
+    }
+
+    #[test]
+    fn only_a_limited_number_of_torches_can_be_built() {
+        let mut app = App::new();
+        app.add_event::<BuildTorch>()
+            .insert_resource(Inventory {
+                coins: Item::new(1000, 1000),
+                mana: Item::new(0, 10),
+            })
+            .add_systems(Update, build_torch);
+
+        for x in 0..MAX_TORCHES + 2 {
+            app.world_mut().send_event(BuildTorch { x: x as f32 });
+        }
+        app.update();
+
+        let mut torch_query = app.world_mut().query::<&Torch>();
+        assert_eq!(torch_query.iter(app.world()).count(), MAX_TORCHES);
+        assert_eq!(
+            app.world().resource::<Inventory>().coins.count(),
+            1000 - MAX_TORCHES as u32 * TORCH_COST
+        );
+    }
+}
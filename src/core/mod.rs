@@ -3,52 +3,113 @@
 //! Everything else depends on this module.
 
 use std::fmt::Display;
-use std::time::Duration;
 
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::prelude::*;
 use bevy_turborand::prelude::*;
 use stats::AttackStats;
 
 use self::{
+    abilities::{Abilities, AbilitiesPlugin, Casting},
+    base_upgrades::{BaseUpgrades, BaseUpgradesPlugin},
+    boss::{Boss, BossPlugin, BossesDefeated, BOSSES_TO_WIN},
+    capture::{spawn_point, CapturePlugin, CapturePoint},
     game_state::GameState,
+    hero::{Hero, HeroPlugin, HeroRespawn},
     inventory::{Inventory, Item},
-    stats::{Health, MovementStats},
+    level::{Lane, Level, LevelPlugin},
+    light::{Concealed, LightPlugin, LightSource, TimeOfDay},
+    neutral::{CreepKilled, NeutralPlugin},
+    recall::{RecallPlugin, Recalling, SelectedUnit},
+    spells::SpellsPlugin,
+    stance::{ArmyStance, RallyPoint, Rallying, StancePlugin},
+    stats::{ArmorType, DamageReduction, Health, MovementLayer, MovementStats, StatModifiers},
+    status_effects::{ApplyStatusEffect, StatusEffectsPlugin, Stunned},
+    support::{Aura, Heal, Healer, SupportPlugin},
+    targeting::{PlayerTargeting, TargetCandidate, TargetingPolicy},
+    veterancy::{Veterancy, VeterancyPlugin, EXPERIENCE_PER_KILL},
+    waves::{WaveScript, WavesPlugin},
+    weather::{Weather, WeatherPlugin},
 };
 
+pub mod abilities;
+pub mod base_upgrades;
+pub mod boss;
+pub mod capture;
 pub mod game_state;
+pub mod hero;
 pub mod inventory;
+pub mod level;
+pub mod light;
+pub mod neutral;
+pub mod recall;
+pub mod spells;
+pub mod stance;
 pub mod stats;
+pub mod status_effects;
+pub mod support;
+pub mod targeting;
+pub mod veterancy;
+pub mod waves;
+pub mod weather;
+
+/// The minimum distance that allied units keep between each other.
+const UNIT_SPACING: f32 = 15.;
 
 pub struct CorePlugin;
 
 impl Plugin for CorePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((RngPlugin::default(), DefaultPlugins))
+        app.add_plugins((RngPlugin::default(), DefaultPlugins, StatusEffectsPlugin))
+            .add_plugins((
+                SupportPlugin,
+                WavesPlugin,
+                BossPlugin,
+                VeterancyPlugin,
+                AbilitiesPlugin,
+                HeroPlugin,
+                SpellsPlugin,
+                StancePlugin,
+                RecallPlugin,
+                BaseUpgradesPlugin,
+                LevelPlugin,
+                WeatherPlugin,
+                LightPlugin,
+                CapturePlugin,
+                NeutralPlugin,
+            ))
             .add_event::<SpawnUnit>()
             .add_event::<Attack>()
             .init_state::<GameState>()
-            .add_systems(OnEnter(GameState::InGame), setup_in_game)
-            .add_systems(
+            .configure_sets(
                 Update,
                 (
-                    (
-                        coin_generation,
-                        generate_waves.run_if(on_timer(Duration::from_secs(5))),
-                    ),
-                    spawn_unit,
-                    (
-                        unit_behavior,
-                        (
-                            move_units,
-                            (attack_animation, attack, die, game_end).chain(),
-                        ),
-                    )
-                        .chain(),
+                    CoreStep::Prepare,
+                    CoreStep::Modifiers,
+                    CoreStep::Behavior,
+                    CoreStep::Act,
+                    CoreStep::Effects,
+                    CoreStep::Resolve,
                 )
                     .chain()
-                    .in_set(CoreSystemSet)
-                    .run_if(in_state(GameState::InGame)),
-            );
+                    .in_set(CoreSystemSet),
+            )
+            .configure_sets(Update, CoreSystemSet.run_if(in_state(GameState::InGame)))
+            .add_systems(
+                OnEnter(GameState::InGame),
+                setup_in_game.in_set(CoreSystemSet),
+            )
+            .add_systems(
+                Update,
+                (coin_generation, spawn_unit, reset_stat_modifiers)
+                    .chain()
+                    .in_set(CoreStep::Prepare),
+            )
+            .add_systems(Update, unit_behavior.in_set(CoreStep::Behavior))
+            .add_systems(
+                Update,
+                (move_units, (attack_animation, attack).chain()).in_set(CoreStep::Act),
+            )
+            .add_systems(Update, (die, game_end).chain().in_set(CoreStep::Resolve));
     }
 }
 
@@ -56,16 +117,43 @@ impl Plugin for CorePlugin {
 
 pub struct CoreSystemSet;
 
+/// The steps of the core game loop, executed in order every frame.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CoreStep {
+    /// Generate resources, spawn new units and reset the stat modifiers.
+    Prepare,
+    /// Accumulate the stat modifiers of the units.
+    Modifiers,
+    /// Decide what the units do next.
+    Behavior,
+    /// Move the units and execute their attacks.
+    Act,
+    /// Apply lasting effects, like status effects.
+    Effects,
+    /// Remove dead units and check if the game is over.
+    Resolve,
+}
+
 #[derive(Debug, Event)]
 pub struct SpawnUnit {
-    pub is_foe: bool,
+    pub faction: Faction,
     pub unit_type: UnitType,
+    /// Where to spawn the unit along the battlefield, at its own base if not set.
+    pub x: Option<f32>,
+    /// The lane to spawn the unit in, a random one for foes and the middle one otherwise if not set.
+    pub lane: Option<usize>,
 }
 
 #[derive(Debug, Event)]
 pub struct Attack {
-    is_foe: bool,
+    /// The unit that attacks, credited with the damage dealt.
+    attacker: Entity,
+    faction: Faction,
+    lane: Option<Lane>,
+    /// The target that the attack was aimed at when it started.
+    target: Entity,
     stats: AttackStats,
+    policy: TargetingPolicy,
     transform: Transform,
     direction: Vec3,
 }
@@ -73,8 +161,45 @@ pub struct Attack {
 #[derive(Debug, Component)]
 pub struct Base;
 
-#[derive(Debug, Component)]
-pub struct Foe;
+/// The side that a unit or base fights for.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Foe,
+    /// Neither side, fighting everyone that comes close.
+    Neutral,
+}
+
+impl Faction {
+    /// Whether units of the two factions fight each other.
+    ///
+    /// Neutral units fight both sides, but not each other.
+    pub fn is_hostile_to(&self, other: Faction) -> bool {
+        *self != other
+    }
+
+    /// The direction in which the faction advances along the battlefield.
+    ///
+    /// Neutral units stay at their camp.
+    pub fn direction(&self) -> f32 {
+        match *self {
+            Self::Player => 1.,
+            Self::Foe => -1.,
+            Self::Neutral => 0.,
+        }
+    }
+
+    /// The direction that a unit at `x` faces when fighting a target at `target_x`.
+    ///
+    /// The two sides face the enemy base, neutral units turn to whoever they fight.
+    pub fn facing(&self, x: f32, target_x: f32) -> f32 {
+        match *self {
+            Self::Neutral if target_x < x => -1.,
+            Self::Neutral => 1.,
+            _ => self.direction(),
+        }
+    }
+}
 
 #[derive(Debug, Component)]
 pub struct Unit;
@@ -89,7 +214,22 @@ pub struct Projectile {
 pub enum UnitType {
     Farmer,
     Archer,
+    Healer,
+    BannerBearer,
+    Catapult,
+    Gryphon,
     Shadow,
+    Ram,
+    Bat,
+    Ghoul,
+    Golem,
+    Wraith,
+    Splitter,
+    Splitling,
+    NightLord,
+    Hero,
+    Wolf,
+    Troll,
 }
 
 impl UnitType {
@@ -97,12 +237,34 @@ impl UnitType {
         match *self {
             Self::Farmer => 10,
             Self::Archer => 20,
+            Self::Healer => 25,
+            Self::BannerBearer => 30,
+            Self::Catapult => 40,
+            Self::Gryphon => 35,
             Self::Shadow => 0,
+            Self::Ram => 0,
+            Self::Bat => 0,
+            Self::Ghoul => 0,
+            Self::Golem => 0,
+            Self::Wraith => 0,
+            Self::Splitter => 0,
+            Self::Splitling => 0,
+            Self::NightLord => 0,
+            Self::Hero => 0,
+            Self::Wolf => 0,
+            Self::Troll => 0,
         }
     }
 
     pub fn player_units() -> Vec<Self> {
-        vec![Self::Farmer, Self::Archer]
+        vec![
+            Self::Farmer,
+            Self::Archer,
+            Self::Healer,
+            Self::BannerBearer,
+            Self::Catapult,
+            Self::Gryphon,
+        ]
     }
 }
 
@@ -111,13 +273,47 @@ impl Display for UnitType {
         let name = match *self {
             Self::Farmer => "Farmer",
             Self::Archer => "Archer",
+            Self::Healer => "Healer",
+            Self::BannerBearer => "Banner Bearer",
+            Self::Catapult => "Catapult",
+            Self::Gryphon => "Gryphon",
             Self::Shadow => "Shadow",
+            Self::Ram => "Ram",
+            Self::Bat => "Bat",
+            Self::Ghoul => "Ghoul",
+            Self::Golem => "Golem",
+            Self::Wraith => "Wraith",
+            Self::Splitter => "Splitter",
+            Self::Splitling => "Splitling",
+            Self::NightLord => "Night Lord",
+            Self::Hero => "Hero",
+            Self::Wolf => "Wolf",
+            Self::Troll => "Troll",
         };
 
         write!(f, "{name}")
     }
 }
 
+/// Spawns smaller units in its place when it dies.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct SplitsOnDeath {
+    pub unit_type: UnitType,
+    pub count: u32,
+}
+
+impl SplitsOnDeath {
+    pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+        match unit_type {
+            UnitType::Splitter => Some(Self {
+                unit_type: UnitType::Splitling,
+                count: 3,
+            }),
+            _ => None,
+        }
+    }
+}
+
 #[derive(States, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Winner {
     Player,
@@ -131,19 +327,68 @@ pub struct GameStats {
 
 #[derive(Debug, Component)]
 pub enum Attacking {
-    Start,
-    Foreswing(Timer),
+    Start { target: Entity },
+    Foreswing { target: Entity, timer: Timer },
     Backswing(Timer),
 }
 
 fn setup_in_game(mut commands: Commands, mut global_rng: ResMut<GlobalRng>) {
     commands.insert_resource(Inventory {
         coins: Item::empty(100),
+        mana: Item::empty(50),
     });
+    commands.insert_resource(PlayerTargeting::default());
+    commands.insert_resource(WaveScript::default());
+    commands.insert_resource(BossesDefeated::default());
+    commands.insert_resource(HeroRespawn::default());
+    commands.insert_resource(ArmyStance::default());
+    commands.insert_resource(RallyPoint::default());
+    commands.insert_resource(SelectedUnit::default());
+    commands.insert_resource(BaseUpgrades::default());
+
+    let level = Level::default();
+    let weather = level
+        .weather
+        .unwrap_or_else(|| Weather::random(&mut global_rng));
+    let time_of_day = level
+        .time_of_day
+        .unwrap_or_else(|| TimeOfDay::random(&mut global_rng));
+    commands.insert_resource(weather);
+    commands.insert_resource(time_of_day);
+
+    for (x, lane, reward) in level.capture_points.iter() {
+        let y = level.lanes.get(*lane).copied().unwrap_or_default();
+
+        commands.spawn((
+            CapturePoint::new(*reward),
+            Lane(*lane),
+            TransformBundle {
+                local: Transform::from_xyz(*x, y, -15.),
+                ..default()
+            },
+            VisibilityBundle::default(),
+        ));
+    }
+
+    for (x, camp) in level.creep_camps.iter() {
+        commands.spawn((
+            camp.clone(),
+            TransformBundle {
+                local: Transform::from_xyz(*x, 0., 0.),
+                ..default()
+            },
+        ));
+    }
+
+    commands.insert_resource(level);
 
     commands.spawn((
         Base,
+        Faction::Player,
         Health::from_max(100.),
+        ArmorType::Fortified,
+        DamageReduction::default(),
+        LightSource::base(),
         RngComponent::from(&mut global_rng),
         TransformBundle {
             local: Transform::from_xyz(-200., 0., -10.),
@@ -154,8 +399,10 @@ fn setup_in_game(mut commands: Commands, mut global_rng: ResMut<GlobalRng>) {
 
     commands.spawn((
         Base,
-        Foe,
+        Faction::Foe,
         Health::from_max(100.),
+        ArmorType::Fortified,
+        DamageReduction::default(),
         RngComponent::from(&mut global_rng),
         TransformBundle {
             local: Transform::from_xyz(200., 0., -10.),
@@ -169,135 +416,519 @@ fn coin_generation(mut inventory: ResMut<Inventory>, time: Res<Time>) {
     inventory.coins.add_until_full(2. * time.delta_seconds());
 }
 
-fn generate_waves(mut spawn_unit_event: EventWriter<SpawnUnit>) {
-    spawn_unit_event.send(SpawnUnit {
-        is_foe: true,
-        unit_type: UnitType::Shadow,
-    });
+/// The components that every unit has, no matter how it entered the battlefield.
+pub fn unit_bundle(
+    unit_type: UnitType,
+    faction: Faction,
+    lane: usize,
+    transform: Transform,
+    rng_component: RngComponent,
+) -> impl Bundle {
+    (
+        (Unit, faction, unit_type, Lane(lane)),
+        MovementStats::from(unit_type),
+        AttackStats::from(unit_type),
+        Health::from(unit_type),
+        ArmorType::from(unit_type),
+        MovementLayer::from(unit_type),
+        StatModifiers::default(),
+        Veterancy::default(),
+        TargetingPolicy::from(unit_type),
+        rng_component,
+        TransformBundle {
+            local: transform,
+            ..default()
+        },
+        VisibilityBundle::default(),
+    )
 }
 
 fn spawn_unit(
     mut spawn_unit_event: EventReader<SpawnUnit>,
     mut commands: Commands,
     mut global_rng: ResMut<GlobalRng>,
-    friend_base: Query<&Transform, (With<Base>, Without<Foe>)>,
-    foe_base: Query<&Transform, (With<Base>, With<Foe>)>,
+    base_query: Query<(&Transform, &Faction), With<Base>>,
+    capture_query: Query<(&Transform, &CapturePoint, Option<&Lane>)>,
+    rally_point: Res<RallyPoint>,
+    level: Res<Level>,
 ) {
-    for SpawnUnit { is_foe, unit_type } in spawn_unit_event.read() {
+    for SpawnUnit {
+        faction,
+        unit_type,
+        x,
+        lane,
+    } in spawn_unit_event.read()
+    {
         let mut rng_component = RngComponent::from(&mut global_rng);
 
-        let base_transform = if *is_foe {
-            foe_base.get_single()
-        } else {
-            friend_base.get_single()
-        };
-        let Ok(base_transform) = base_transform else {
+        let base_transform = base_query
+            .iter()
+            .find(|(_, base_faction)| *base_faction == faction)
+            .map(|(transform, _)| transform);
+        let Some(base_transform) = base_transform else {
             continue;
         };
         let mut transform = *base_transform;
 
+        // Foes spread out across all lanes
+        let lane = match lane {
+            Some(lane) => (*lane).min(level.lanes.len().saturating_sub(1)),
+            None if *faction == Faction::Foe => rng_component.usize(0..level.lanes.len().max(1)),
+            None => level.default_lane(),
+        };
+
+        // Without a given position, units spawn at the most forward point held in their lane
+        let capture_points = capture_query
+            .iter()
+            .filter(|(_, _, point_lane)| Lane::meets(Some(&Lane(lane)), *point_lane))
+            .map(|(transform, point, _)| (transform.translation.x, point));
+
+        if let Some(x) = x.or_else(|| spawn_point(*faction, capture_points)) {
+            transform.translation.x = x;
+        }
+
         transform.translation.z += 100. + rng_component.f32() * 10.;
+        transform.translation.y += level.lanes.get(lane).copied().unwrap_or_default();
         transform.translation.y += rng_component.f32() * 2.;
 
         let id = commands
-            .spawn((
-                Unit,
-                MovementStats::from(*unit_type),
-                AttackStats::from(*unit_type),
-                Health::from(*unit_type),
+            .spawn(unit_bundle(
                 *unit_type,
+                *faction,
+                lane,
+                transform,
                 rng_component,
-                TransformBundle {
-                    local: transform,
-                    ..default()
-                },
-                VisibilityBundle::default(),
             ))
             .id();
 
-        if *is_foe {
-            commands.entity(id).insert(Foe);
+        if *faction == Faction::Player {
+            commands.entity(id).insert(LightSource::unit());
+        }
+
+        if let Some(healer) = Healer::for_unit(*unit_type) {
+            commands.entity(id).insert(healer);
+        }
+
+        if let Some(aura) = Aura::for_unit(*unit_type) {
+            commands.entity(id).insert(aura);
+        }
+
+        if let Some(splits_on_death) = SplitsOnDeath::for_unit(*unit_type) {
+            commands.entity(id).insert(splits_on_death);
+        }
+
+        if let Some(boss) = Boss::for_unit(*unit_type) {
+            commands.entity(id).insert(boss);
+        }
+
+        if let Some(abilities) = Abilities::for_unit(*unit_type) {
+            commands.entity(id).insert(abilities);
+        }
+
+        if matches!(unit_type, UnitType::Hero) {
+            commands.entity(id).insert(Hero::default());
+        } else if let (Faction::Player, Some(x)) = (faction, rally_point.0) {
+            commands.entity(id).insert(Rallying { x });
         }
     }
 }
 
+fn reset_stat_modifiers(mut unit_query: Query<&mut StatModifiers>) {
+    for mut modifiers in unit_query.iter_mut() {
+        *modifiers = StatModifiers::default();
+    }
+}
+
 fn unit_behavior(
     mut commands: Commands,
-    unit_query: Query<
-        (Entity, &Transform, &AttackStats, Has<Foe>),
-        (With<Unit>, Without<Attacking>),
+    player_targeting: Res<PlayerTargeting>,
+    mut unit_query: Query<
+        (
+            Entity,
+            &Transform,
+            &AttackStats,
+            &StatModifiers,
+            &TargetingPolicy,
+            Option<&Healer>,
+            Option<&mut Abilities>,
+            Option<&Lane>,
+            &Faction,
+        ),
+        (
+            With<Unit>,
+            Without<Hero>,
+            Without<Attacking>,
+            Without<Casting>,
+            Without<Stunned>,
+            Without<Recalling>,
+        ),
+    >,
+    other_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Health,
+            Option<&AttackStats>,
+            Option<&MovementLayer>,
+            Option<&Lane>,
+            &Faction,
+            Has<Base>,
+        ),
+        Or<(With<Unit>, With<Base>)>,
     >,
-    other_query: Query<(&Transform, Has<Foe>), Or<(With<Unit>, With<Base>)>>,
+    concealed_query: Query<(), With<Concealed>>,
+    stance: Res<ArmyStance>,
 ) {
-    for (entity, transform, stats, is_foe) in unit_query.iter() {
-        let direction = if is_foe { -1. } else { 1. };
+    for (entity, transform, stats, modifiers, policy, healer, abilities, lane, faction) in
+        unit_query.iter_mut()
+    {
+        // Retreating units don't start new fights
+        if *faction == Faction::Player && *stance == ArmyStance::Retreat {
+            continue;
+        }
+
+        let stats = stats.with_modifiers(modifiers);
+        let policy = player_targeting.policy_for(*policy, *faction);
+
+        if healer.is_some() {
+            // Heal the most injured ally in range, no matter if in front or behind
+            let target = other_query
+                .iter()
+                .filter(
+                    |(other, other_transform, health, _, _, other_lane, other_faction, is_base)| {
+                        let distance =
+                            (other_transform.translation.x - transform.translation.x).abs();
+
+                        *other != entity
+                            && !faction.is_hostile_to(**other_faction)
+                            && Lane::meets(lane, *other_lane)
+                            && !is_base
+                            && health.is_injured()
+                            && distance <= stats.attack_range
+                    },
+                )
+                .min_by(
+                    |(_, _, a_health, _, _, _, _, _), (_, _, b_health, _, _, _, _, _)| {
+                        let a_fraction = a_health.current() / a_health.max();
+                        let b_fraction = b_health.current() / b_health.max();
+
+                        a_fraction.total_cmp(&b_fraction)
+                    },
+                );
 
-        let is_in_attack_range = other_query.iter().any(|(other_transform, is_other_foe)| {
-            if is_foe == is_other_foe {
-                // Only attack units from the other fraction
-                return false;
+            if let Some((target, _, _, _, _, _, _, _)) = target {
+                commands.entity(entity).insert(Attacking::Start { target });
             }
 
-            let x = transform.translation.x;
-            let other_x = other_transform.translation.x;
+            continue;
+        }
+
+        let candidates: Vec<TargetCandidate> = other_query
+            .iter()
+            // Only attack hostile units in the same lane
+            .filter(|(_, _, _, _, _, other_lane, other_faction, _)| {
+                faction.is_hostile_to(**other_faction) && Lane::meets(lane, *other_lane)
+            })
+            .map(
+                |(other, other_transform, health, other_stats, layer, _, _, is_base)| {
+                    TargetCandidate::new(
+                        other,
+                        other_transform,
+                        health,
+                        other_stats,
+                        layer,
+                        is_base,
+                        concealed_query.contains(other),
+                    )
+                },
+            )
+            .collect();
+
+        // Neutral units turn towards whoever comes closest
+        let x = transform.translation.x;
+        let closest_x = candidates
+            .iter()
+            .map(|candidate| candidate.translation.x)
+            .min_by(|a, b| (a - x).abs().total_cmp(&(b - x).abs()))
+            .unwrap_or(x);
+        let direction = faction.facing(x, closest_x);
 
-            let distance = other_x - x;
+        // Prefer using an ability over a normal attack
+        if let Some(mut abilities) = abilities {
+            let casting = abilities.trigger(
+                transform.translation,
+                direction,
+                policy,
+                modifiers,
+                &candidates,
+            );
 
-            if distance.signum() != direction {
-                // Only attack units in front of you
-                return false;
+            if let Some(casting) = casting {
+                // An enemy in range ends waiting at the rally point
+                commands.entity(entity).insert(casting).remove::<Rallying>();
+                continue;
             }
+        }
 
-            // Only attack units within the attack range
-            distance.abs() <= stats.attack_range
-        });
+        let target = policy.select(
+            transform.translation,
+            direction,
+            stats.range(),
+            candidates
+                .into_iter()
+                .filter(|candidate| candidate.is_reachable_by(&stats)),
+        );
 
-        if is_in_attack_range {
-            commands.entity(entity).insert(Attacking::Start);
+        if let Some(target) = target {
+            commands
+                .entity(entity)
+                .insert(Attacking::Start {
+                    target: target.entity,
+                })
+                .remove::<Rallying>();
         }
     }
 }
 
 fn move_units(
     mut unit_query: Query<
-        (&mut Transform, &MovementStats, Has<Foe>),
-        (With<Unit>, Without<Attacking>),
+        (
+            Entity,
+            &mut Transform,
+            &MovementStats,
+            &StatModifiers,
+            Option<&MovementLayer>,
+            Option<&Lane>,
+            Option<&Rallying>,
+            Has<Recalling>,
+            &Faction,
+            Has<Attacking>,
+            Has<Casting>,
+            Has<Stunned>,
+        ),
+        (With<Unit>, Without<Hero>),
     >,
+    base_query: Query<(&Transform, &Faction), (With<Base>, Without<Unit>)>,
+    stance: Res<ArmyStance>,
+    level: Res<Level>,
     time: Res<Time>,
 ) {
-    for (mut transform, stats, is_foe) in unit_query.iter_mut() {
-        let direction = if is_foe { -1. } else { 1. };
+    let base_x = base_query
+        .iter()
+        .find(|(_, faction)| **faction == Faction::Player)
+        .map_or(0., |(transform, _)| transform.translation.x);
+
+    let positions: Vec<UnitPosition> = unit_query
+        .iter()
+        .map(
+            |(entity, transform, _, _, layer, lane, _, is_recalling, faction, _, _, _)| {
+                UnitPosition {
+                    entity,
+                    x: transform.translation.x,
+                    layer: layer.copied().unwrap_or_default(),
+                    lane: lane.copied(),
+                    faction: *faction,
+                    is_recalling,
+                }
+            },
+        )
+        .collect();
+
+    for (
+        entity,
+        mut transform,
+        stats,
+        modifiers,
+        layer,
+        lane,
+        rallying,
+        is_recalling,
+        faction,
+        is_attacking,
+        is_casting,
+        is_stunned,
+    ) in unit_query.iter_mut()
+    {
+        let layer = layer.copied().unwrap_or_default();
+
+        // Neutral units stay at their camp
+        if *faction == Faction::Neutral || is_attacking || is_casting || is_stunned {
+            continue;
+        }
+
+        let x = transform.translation.x;
+        // Holding or retreating overrides waiting at the rally point
+        let destination = if *faction == Faction::Foe {
+            None
+        } else if is_recalling {
+            Some(base_x)
+        } else {
+            stance
+                .destination(base_x)
+                .or(rallying.map(|rallying| rallying.x))
+        };
+
+        let direction = match destination {
+            Some(destination) if destination == x => continue,
+            Some(destination) => (destination - x).signum(),
+            None => faction.direction(),
+        };
+
+        let position = UnitPosition {
+            entity,
+            x,
+            layer,
+            lane: lane.copied(),
+            faction: *faction,
+            is_recalling,
+        };
+        let ally_distance = position.ally_distance(direction, &positions);
+
+        let mut step = stats.speed * modifiers.speed * time.delta_seconds();
+
+        // Ground units pass narrow terrain like bridges in single file
+        let spacing = match (layer, level.terrain_at(x)) {
+            (MovementLayer::Ground, Some(terrain)) => UNIT_SPACING * terrain.spacing(),
+            _ => UNIT_SPACING,
+        };
+
+        if let Some(ally_distance) = ally_distance {
+            step = step.min((ally_distance - spacing).max(0.));
+        }
 
-        transform.translation += Vec3::new(direction, 0., 0.) * stats.speed * time.delta_seconds();
+        // Don't overshoot the destination
+        if let Some(destination) = destination {
+            step = step.min((destination - x).abs());
+        }
+
+        transform.translation.x += direction * step;
+    }
+}
+
+/// Where a unit is, as seen by the allies moving around it.
+#[derive(Debug, Clone, Copy)]
+struct UnitPosition {
+    entity: Entity,
+    x: f32,
+    layer: MovementLayer,
+    lane: Option<Lane>,
+    faction: Faction,
+    is_recalling: bool,
+}
+
+impl UnitPosition {
+    /// The distance to the closest ally in front when moving in the direction, e.g. a unit that is fighting.
+    ///
+    /// Flying units pass over units on the ground and the other way around.
+    /// Recalling units slip through the ranks, they neither block nor get blocked.
+    fn ally_distance(&self, direction: f32, positions: &[UnitPosition]) -> Option<f32> {
+        if self.is_recalling {
+            return None;
+        }
+
+        positions
+            .iter()
+            .filter(|other| {
+                if other.entity == self.entity
+                    || other.is_recalling
+                    || other.faction != self.faction
+                    || other.layer != self.layer
+                    || !Lane::meets(self.lane.as_ref(), other.lane.as_ref())
+                {
+                    return false;
+                }
+
+                let distance = (other.x - self.x) * direction;
+
+                // Break ties between units at the same position, so they don't block each other
+                distance > 0. || (distance == 0. && other.entity < self.entity)
+            })
+            .map(|other| (other.x - self.x) * direction)
+            .min_by(|a, b| a.total_cmp(b))
     }
 }
 
 fn attack_animation(
     mut commands: Commands,
     mut attack_event: EventWriter<Attack>,
-    mut unit_query: Query<(Entity, &mut Attacking, &Transform, &AttackStats, Has<Foe>), With<Unit>>,
+    mut heal_event: EventWriter<Heal>,
+    player_targeting: Res<PlayerTargeting>,
+    mut unit_query: Query<
+        (
+            Entity,
+            &mut Attacking,
+            &Transform,
+            &AttackStats,
+            &StatModifiers,
+            &TargetingPolicy,
+            Option<&Healer>,
+            Option<&Lane>,
+            &Faction,
+        ),
+        With<Unit>,
+    >,
+    target_query: Query<(&Health, &Transform)>,
     time: Res<Time>,
 ) {
-    for (entity, mut attacking, transform, attack_stats, is_foe) in unit_query.iter_mut() {
+    for (
+        entity,
+        mut attacking,
+        transform,
+        attack_stats,
+        modifiers,
+        policy,
+        healer,
+        lane,
+        faction,
+    ) in unit_query.iter_mut()
+    {
+        let attack_stats = attack_stats.with_modifiers(modifiers);
+
         match &mut *attacking {
-            Attacking::Start => {
+            Attacking::Start { target } => {
                 // Start the foreswing anymation
-                *attacking = Attacking::Foreswing(Timer::from_seconds(1., TimerMode::Once))
+                *attacking = Attacking::Foreswing {
+                    target: *target,
+                    timer: Timer::from_seconds(attack_stats.foreswing_secs(), TimerMode::Once),
+                }
             }
-            Attacking::Foreswing(ref mut timer) => {
-                if timer.tick(time.delta()).finished() {
+            Attacking::Foreswing { target, timer } => {
+                let is_target_dead = target_query
+                    .get(*target)
+                    .map_or(true, |(health, _)| health.is_dead());
+
+                if is_target_dead && attack_stats.cancel_on_target_death {
+                    // Stop the attack, so the unit can look for a new target
+                    commands.entity(entity).remove::<Attacking>();
+                } else if timer.tick(time.delta()).finished() {
                     // After the foreswing is complete, execute the attack and start the backswing
-                    attack_event.send(Attack {
-                        direction: if is_foe {
-                            Vec3::new(-1., 0., 0.)
-                        } else {
-                            Vec3::new(1., 0., 0.)
-                        },
-                        is_foe,
-                        transform: *transform,
-                        stats: attack_stats.clone(),
-                    });
-                    *attacking = Attacking::Backswing(Timer::from_seconds(0.5, TimerMode::Once));
+                    if let Some(healer) = healer {
+                        heal_event.send(Heal {
+                            target: *target,
+                            amount: healer.amount,
+                        });
+                    } else {
+                        let x = transform.translation.x;
+                        let target_x = target_query
+                            .get(*target)
+                            .map_or(x, |(_, target_transform)| target_transform.translation.x);
+                        let direction = faction.facing(x, target_x);
+
+                        attack_event.send(Attack {
+                            attacker: entity,
+                            direction: Vec3::new(direction, 0., 0.),
+                            faction: *faction,
+                            lane: lane.copied(),
+                            target: *target,
+                            transform: *transform,
+                            policy: player_targeting.policy_for(*policy, *faction),
+                            stats: attack_stats.clone(),
+                        });
+                    }
+                    *attacking = Attacking::Backswing(Timer::from_seconds(
+                        attack_stats.backswing_secs(),
+                        TimerMode::Once,
+                    ));
                 }
             }
             Attacking::Backswing(ref mut timer) => {
@@ -312,54 +943,184 @@ fn attack_animation(
 
 fn attack(
     mut attack_event: EventReader<Attack>,
-    mut target_query: Query<(&Transform, Has<Foe>, &mut Health)>,
+    mut apply_status_effect_event: EventWriter<ApplyStatusEffect>,
+    mut creep_killed_event: EventWriter<CreepKilled>,
+    mut target_query: Query<(
+        Entity,
+        &Transform,
+        &Faction,
+        Has<Base>,
+        &mut Health,
+        Option<&ArmorType>,
+        Option<&AttackStats>,
+        Option<&MovementLayer>,
+        Option<&Lane>,
+    )>,
+    mut veterancy_query: Query<&mut Veterancy>,
+    reduction_query: Query<&DamageReduction>,
+    concealed_query: Query<(), With<Concealed>>,
 ) {
     for Attack {
-        is_foe,
+        attacker,
+        faction,
+        lane,
+        target,
         stats: unit_stats,
+        policy,
         transform,
         direction,
     } in attack_event.read()
     {
-        let closest_unit = target_query
-            .iter_mut()
-            .filter(|(other_transform, is_other_foe, _)| {
-                if is_foe == is_other_foe {
-                    return false;
-                }
+        let enemies: Vec<TargetCandidate> = target_query
+            .iter()
+            .filter(|(_, _, other_faction, _, _, _, _, _, other_lane)| {
+                faction.is_hostile_to(**other_faction) && Lane::meets(lane.as_ref(), *other_lane)
+            })
+            .map(
+                |(entity, other_transform, _, is_base, health, _, other_stats, layer, _)| {
+                    TargetCandidate::new(
+                        entity,
+                        other_transform,
+                        health,
+                        other_stats,
+                        layer,
+                        is_base,
+                        concealed_query.contains(entity),
+                    )
+                },
+            )
+            .filter(|enemy| enemy.is_reachable_by(unit_stats))
+            .collect();
 
-                let x = transform.translation.x;
-                let other_x = other_transform.translation.x;
+        let origin = transform.translation;
+        let range = unit_stats.range();
 
-                let distance = other_x - x;
+        // Hit the original target if it's still in range, otherwise whoever the policy picks
+        let Some(TargetCandidate {
+            entity: target,
+            translation: target_translation,
+            ..
+        }) = policy
+            .select(
+                origin,
+                direction.x,
+                range.clone(),
+                enemies
+                    .iter()
+                    .copied()
+                    .filter(|enemy| enemy.entity == *target),
+            )
+            .or_else(|| policy.select(origin, direction.x, range, enemies.iter().copied()))
+        else {
+            continue;
+        };
 
-                if distance.signum() != direction.x {
-                    // Only attack units in front of you
-                    return false;
+        // The target is always hit, other enemies only if they are in the area of the attack
+        let mut hits: Vec<(Entity, f32)> = enemies
+            .iter()
+            .filter(|enemy| enemy.entity != target)
+            .filter_map(|enemy| {
+                unit_stats
+                    .area
+                    .coverage(
+                        transform.translation.truncate(),
+                        direction.truncate(),
+                        unit_stats.attack_range,
+                        target_translation.truncate(),
+                        enemy.translation.truncate(),
+                    )
+                    .map(|coverage| (enemy.entity, coverage))
+            })
+            .collect();
+        hits.sort_by(|(_, a_coverage), (_, b_coverage)| a_coverage.total_cmp(b_coverage));
+        hits.insert(0, (target, 0.));
+
+        if let Some(max_targets) = unit_stats.max_targets {
+            hits.truncate(max_targets);
+        }
+
+        let mut experience = 0.;
+
+        for (target, coverage) in hits {
+            let Ok((_, _, target_faction, _, mut health, armor, _, _, _)) =
+                target_query.get_mut(target)
+            else {
+                continue;
+            };
+
+            let armor = armor.copied().unwrap_or(ArmorType::Unarmored);
+            let falloff = 1. - unit_stats.falloff * coverage;
+            let mut damage = unit_stats.damage_against(armor) * falloff;
+
+            if let Ok(reduction) = reduction_query.get(target) {
+                damage = reduction.apply(damage);
+            }
+
+            // Only the damage that was actually taken counts, overkill doesn't
+            if !health.is_dead() {
+                experience += damage.min(health.current());
+                health.apply_damage(damage);
+
+                if health.is_dead() {
+                    experience += EXPERIENCE_PER_KILL;
+
+                    if *target_faction == Faction::Neutral {
+                        creep_killed_event.send(CreepKilled {
+                            killer: *attacker,
+                            faction: *faction,
+                        });
+                    }
                 }
+            }
 
-                // Only attack units within the attack range
-                distance.abs() <= unit_stats.attack_range
-            })
-            .min_by(|(a_transform, _, _), (b_transform, _, _)| {
-                let a_distance = transform.translation.distance(a_transform.translation);
-                let b_distance = transform.translation.distance(b_transform.translation);
+            // A killing blow doesn't leave any effects on the corpse
+            if health.is_dead() {
+                continue;
+            }
 
-                a_distance
-                    .partial_cmp(&b_distance)
-                    .unwrap_or(std::cmp::Ordering::Less)
-            });
+            if let Some(effect) = unit_stats.status_effect {
+                apply_status_effect_event.send(ApplyStatusEffect { target, effect });
+            }
+        }
 
-        if let Some((_, _, mut health)) = closest_unit {
-            health.apply_damage(unit_stats.attack_damage);
+        if let Ok(mut veterancy) = veterancy_query.get_mut(*attacker) {
+            veterancy.gain(experience);
         }
     }
 }
 
-fn die(mut commands: Commands, unit_query: Query<(Entity, &Health)>) {
-    for (unit, health) in unit_query.iter() {
+fn die(
+    mut commands: Commands,
+    mut spawn_unit_event: EventWriter<SpawnUnit>,
+    mut bosses_defeated: ResMut<BossesDefeated>,
+    unit_query: Query<(
+        Entity,
+        &Health,
+        &Transform,
+        Option<&SplitsOnDeath>,
+        Option<&Lane>,
+        Has<Boss>,
+        &Faction,
+    )>,
+) {
+    for (unit, health, transform, splits_on_death, lane, is_boss, faction) in unit_query.iter() {
         if health.is_dead() {
             commands.entity(unit).despawn_recursive();
+
+            if is_boss && *faction == Faction::Foe {
+                bosses_defeated.0 += 1;
+            }
+
+            if let Some(SplitsOnDeath { unit_type, count }) = splits_on_death {
+                for _ in 0..*count {
+                    spawn_unit_event.send(SpawnUnit {
+                        faction: *faction,
+                        unit_type: *unit_type,
+                        x: Some(transform.translation.x),
+                        lane: lane.map(|lane| lane.0),
+                    });
+                }
+            }
         }
     }
 }
@@ -367,12 +1128,18 @@ fn die(mut commands: Commands, unit_query: Query<(Entity, &Health)>) {
 fn game_end(
     mut commands: Commands,
     mut next_state: ResMut<NextState<GameState>>,
-    foe_bases: Query<Entity, (With<Base>, With<Foe>)>,
-    player_bases: Query<Entity, (With<Base>, Without<Foe>)>,
+    bosses_defeated: Res<BossesDefeated>,
+    base_query: Query<&Faction, With<Base>>,
 ) {
-    let winner = if foe_bases.iter().count() == 0 {
+    let has_base = |faction| {
+        base_query
+            .iter()
+            .any(|base_faction| *base_faction == faction)
+    };
+
+    let winner = if !has_base(Faction::Foe) || bosses_defeated.0 >= BOSSES_TO_WIN {
         Some(Winner::Player)
-    } else if player_bases.iter().count() == 0 {
+    } else if !has_base(Faction::Player) {
         Some(Winner::Enemy)
     } else {
         None
@@ -383,3 +1150,194 @@ fn game_end(
         next_state.set(GameState::PostGame);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::status_effects::{StatusEffect, StatusEffectKind};
+
+    use super::*;
+
+    fn position(index: u32, x: f32, is_recalling: bool) -> UnitPosition {
+        UnitPosition {
+            entity: Entity::from_raw(index),
+            x,
+            layer: MovementLayer::Ground,
+            lane: Some(Lane(0)),
+            faction: Faction::Player,
+            is_recalling,
+        }
+    }
+
+    #[test]
+    fn units_keep_their_distance_to_the_nearest_ally_ahead() {
+        let unit = position(0, 0., false);
+        let positions = [
+            unit,
+            position(1, 30., false),
+            position(2, 10., false),
+            position(3, -5., false),
+        ];
+
+        assert_eq!(unit.ally_distance(1., &positions), Some(10.));
+        assert_eq!(unit.ally_distance(-1., &positions), Some(5.));
+    }
+
+    #[test]
+    fn units_only_make_room_for_allies() {
+        let unit = position(0, 0., false);
+        let enemy = UnitPosition {
+            faction: Faction::Foe,
+            ..position(1, 5., false)
+        };
+        let positions = [unit, enemy];
+
+        assert_eq!(unit.ally_distance(1., &positions), None);
+    }
+
+    #[test]
+    fn flying_units_pass_over_units_on_the_ground() {
+        let unit = position(0, 0., false);
+        let flying = UnitPosition {
+            layer: MovementLayer::Air,
+            ..position(1, 5., false)
+        };
+        let positions = [unit, flying];
+
+        assert_eq!(unit.ally_distance(1., &positions), None);
+        assert_eq!(flying.ally_distance(-1., &positions), None);
+    }
+
+    #[test]
+    fn units_only_make_room_for_allies_in_their_lane() {
+        let unit = position(0, 0., false);
+        let other_lane = UnitPosition {
+            lane: Some(Lane(1)),
+            ..position(1, 5., false)
+        };
+        let any_lane = UnitPosition {
+            lane: None,
+            ..position(2, 10., false)
+        };
+        let positions = [unit, other_lane, any_lane];
+
+        assert_eq!(unit.ally_distance(1., &positions), Some(10.));
+    }
+
+    #[test]
+    fn only_one_of_two_units_at_the_same_position_waits() {
+        let first = position(0, 0., false);
+        let second = position(1, 0., false);
+        let positions = [first, second];
+
+        assert_eq!(first.ally_distance(1., &positions), None);
+        assert_eq!(second.ally_distance(1., &positions), Some(0.));
+    }
+
+    #[test]
+    fn recalling_units_pass_allies_on_the_way_to_the_base() {
+        // The base is to the left, an ally stands between it and the recalled unit
+        let recalled = position(0, 50., true);
+        let ally = position(1, 40., false);
+        let positions = [recalled, ally];
+
+        assert_eq!(recalled.ally_distance(-1., &positions), None);
+    }
+
+    #[test]
+    fn recalling_units_do_not_block_allies() {
+        let unit = position(0, 0., false);
+        let recalled = position(1, 10., true);
+        let positions = [unit, recalled];
+
+        assert_eq!(unit.ally_distance(1., &positions), None);
+    }
+
+    #[test]
+    fn only_surviving_targets_get_on_hit_effects() {
+        let mut app = App::new();
+        app.add_event::<Attack>()
+            .add_event::<ApplyStatusEffect>()
+            .add_event::<CreepKilled>()
+            .add_systems(Update, attack);
+
+        let stats = AttackStats {
+            attack_damage: 5.,
+            status_effect: Some(StatusEffect {
+                kind: StatusEffectKind::Poison(1.),
+                duration: 2.,
+            }),
+            ..default()
+        };
+        let mut hit = |health: f32| {
+            let target = app
+                .world_mut()
+                .spawn((
+                    Faction::Foe,
+                    Health::from_max(health),
+                    Transform::from_xyz(10., 0., 0.),
+                ))
+                .id();
+            app.world_mut().send_event(Attack {
+                attacker: Entity::PLACEHOLDER,
+                faction: Faction::Player,
+                lane: None,
+                target,
+                stats: stats.clone(),
+                policy: TargetingPolicy::Closest,
+                transform: Transform::default(),
+                direction: Vec3::X,
+            });
+            app.update();
+
+            let events = app.world().resource::<Events<ApplyStatusEffect>>();
+            let has_effect = events
+                .iter_current_update_events()
+                .any(|event| event.target == target);
+            app.world_mut().despawn(target);
+
+            has_effect
+        };
+
+        assert!(hit(20.));
+        assert!(!hit(5.));
+    }
+
+    #[test]
+    fn a_single_boss_kill_does_not_win_the_game() {
+        let mut app = App::new();
+        app.init_resource::<NextState<GameState>>()
+            .insert_resource(BossesDefeated(1))
+            .add_systems(Update, game_end);
+        app.world_mut().spawn((Base, Faction::Player));
+        app.world_mut().spawn((Base, Faction::Foe));
+
+        app.update();
+        assert!(app.world().get_resource::<GameStats>().is_none());
+
+        app.world_mut().resource_mut::<BossesDefeated>().0 = BOSSES_TO_WIN;
+        app.update();
+        assert!(matches!(
+            app.world().get_resource::<GameStats>(),
+            Some(GameStats {
+                winner: Winner::Player
+            })
+        ));
+    }
+
+    #[test]
+    fn neutral_units_fight_both_sides_but_not_each_other() {
+        assert!(Faction::Player.is_hostile_to(Faction::Foe));
+        assert!(Faction::Neutral.is_hostile_to(Faction::Player));
+        assert!(Faction::Foe.is_hostile_to(Faction::Neutral));
+
+        assert!(!Faction::Player.is_hostile_to(Faction::Player));
+        assert!(!Faction::Neutral.is_hostile_to(Faction::Neutral));
+    }
+
+    #[test]
+    fn neutral_units_face_whoever_they_fight() {
+        assert_eq!(Faction::Foe.facing(0., 50.), -1.);
+        assert_eq!(Faction::Neutral.facing(0., -50.), -1.);
+        assert_eq!(Faction::Neutral.facing(0., 50.), 1.);
+    }
+}
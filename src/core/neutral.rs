@@ -0,0 +1,145 @@
+//! Neutral monsters camping between the bases, fighting whoever comes close.
+
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+
+use super::{
+    inventory::Inventory,
+    level::Level,
+    status_effects::{ApplyStatusEffect, StatusEffect, StatusEffectKind},
+    unit_bundle, CoreStep, Faction, UnitType,
+};
+
+/// How long a cleared camp stays empty before its monsters come back.
+const CAMP_RESPAWN_SECS: f32 = 45.;
+
+/// The coins the player gets for each monster killed.
+const CREEP_BOUNTY: f32 = 8.;
+
+/// The buff for the unit that killed a monster.
+const CREEP_KILL_RALLY: f32 = 1.2;
+const CREEP_KILL_RALLY_SECS: f32 = 10.;
+
+/// The distance between the monsters of a camp.
+const CREEP_SPACING: f32 = 12.;
+
+pub struct NeutralPlugin;
+
+impl Plugin for NeutralPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CreepKilled>()
+            .add_systems(Update, respawn_camps.in_set(CoreStep::Prepare))
+            .add_systems(Update, reward_creep_kills.in_set(CoreStep::Effects));
+    }
+}
+
+/// A spot on the battlefield where neutral monsters gather.
+#[derive(Debug, Component, Clone)]
+pub struct CreepCamp {
+    pub unit_type: UnitType,
+    pub count: usize,
+    pub lane: usize,
+    /// Counts down while the camp is cleared, the monsters spawn when it's finished.
+    pub respawn: Timer,
+}
+
+impl CreepCamp {
+    pub fn new(unit_type: UnitType, count: usize, lane: usize) -> Self {
+        // The camp is filled right away at the start of the battle
+        let mut respawn = Timer::from_seconds(CAMP_RESPAWN_SECS, TimerMode::Once);
+        respawn.tick(respawn.duration());
+
+        Self {
+            unit_type,
+            count,
+            lane,
+            respawn,
+        }
+    }
+}
+
+/// A neutral monster belonging to a camp.
+#[derive(Debug, Component)]
+pub struct Creep {
+    pub camp: Entity,
+}
+
+/// A neutral monster was killed by a unit of one of the two sides.
+#[derive(Debug, Event)]
+pub struct CreepKilled {
+    pub killer: Entity,
+    /// The faction of the killer.
+    pub faction: Faction,
+}
+
+fn respawn_camps(
+    mut commands: Commands,
+    mut global_rng: ResMut<GlobalRng>,
+    mut camp_query: Query<(Entity, &Transform, &mut CreepCamp)>,
+    creep_query: Query<&Creep>,
+    level: Res<Level>,
+    time: Res<Time>,
+) {
+    for (camp, camp_transform, mut creep_camp) in camp_query.iter_mut() {
+        // Wait until the camp is cleared
+        if creep_query.iter().any(|creep| creep.camp == camp) {
+            continue;
+        }
+
+        if !creep_camp.respawn.tick(time.delta()).finished() {
+            continue;
+        }
+
+        creep_camp.respawn.reset();
+
+        let unit_type = creep_camp.unit_type;
+        let lane_y = level
+            .lanes
+            .get(creep_camp.lane)
+            .copied()
+            .unwrap_or_default();
+
+        for index in 0..creep_camp.count {
+            let mut rng_component = RngComponent::from(&mut global_rng);
+
+            // Spread the monsters around the center of the camp
+            let offset = (index as f32 - (creep_camp.count - 1) as f32 / 2.) * CREEP_SPACING;
+            let transform = Transform::from_xyz(
+                camp_transform.translation.x + offset,
+                lane_y + rng_component.f32() * 2.,
+                100. + rng_component.f32() * 10.,
+            );
+
+            commands.spawn((
+                unit_bundle(
+                    unit_type,
+                    Faction::Neutral,
+                    creep_camp.lane,
+                    transform,
+                    rng_component,
+                ),
+                Creep { camp },
+            ));
+        }
+    }
+}
+
+fn reward_creep_kills(
+    mut creep_killed_event: EventReader<CreepKilled>,
+    mut apply_status_effect_event: EventWriter<ApplyStatusEffect>,
+    mut inventory: ResMut<Inventory>,
+) {
+    for CreepKilled { killer, faction } in creep_killed_event.read() {
+        if *faction == Faction::Player {
+            inventory.coins.add_until_full(CREEP_BOUNTY);
+        }
+
+        apply_status_effect_event.send(ApplyStatusEffect {
+            target: *killer,
+            effect: StatusEffect {
+                kind: StatusEffectKind::Rally(CREEP_KILL_RALLY),
+                duration: CREEP_KILL_RALLY_SECS,
+            },
+        });
+    }
+}
@@ -0,0 +1,92 @@
+//! Sending units back to the base for a partial refund.
+
+use bevy::prelude::*;
+
+use super::{
+    hero::Hero, inventory::Inventory, stats::Health, Base, CoreStep, Faction, Unit, UnitType,
+};
+
+/// The fraction of the cost refunded for a recalled unit at full health.
+const REFUND_FRACTION: f32 = 0.5;
+
+/// How close to the base a recalled unit has to get before it leaves the battlefield.
+const ARRIVAL_DISTANCE: f32 = 1.;
+
+pub struct RecallPlugin;
+
+impl Plugin for RecallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Recall>()
+            .add_systems(Update, start_recall.in_set(CoreStep::Prepare))
+            .add_systems(Update, arrive_home.in_set(CoreStep::Resolve));
+    }
+}
+
+/// The player unit selected with the mouse.
+#[derive(Debug, Resource, Default)]
+pub struct SelectedUnit(pub Option<Entity>);
+
+/// A unit walking back to its base, not fighting anymore.
+#[derive(Debug, Component)]
+pub struct Recalling;
+
+/// Send a player unit back to the base.
+#[derive(Debug, Event)]
+pub struct Recall {
+    pub unit: Entity,
+}
+
+/// The coins refunded for a recalled unit, depending on how hurt it is.
+pub fn refund(unit_type: UnitType, health: &Health) -> f32 {
+    let health_fraction = (health.current() / health.max()).clamp(0., 1.);
+
+    (unit_type.cost() as f32 * REFUND_FRACTION * health_fraction).floor()
+}
+
+fn start_recall(
+    mut commands: Commands,
+    mut recall_event: EventReader<Recall>,
+    unit_query: Query<&Faction, (With<Unit>, Without<Hero>)>,
+) {
+    for Recall { unit } in recall_event.read() {
+        if unit_query.get(*unit) == Ok(&Faction::Player) {
+            commands.entity(*unit).try_insert(Recalling);
+        }
+    }
+}
+
+fn arrive_home(
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    base_query: Query<(&Transform, &Faction), With<Base>>,
+    unit_query: Query<(Entity, &Transform, &UnitType, &Health), With<Recalling>>,
+) {
+    let Some((base_transform, _)) = base_query
+        .iter()
+        .find(|(_, faction)| **faction == Faction::Player)
+    else {
+        return;
+    };
+
+    for (unit, transform, unit_type, health) in unit_query.iter() {
+        let distance = (transform.translation.x - base_transform.translation.x).abs();
+
+        if distance <= ARRIVAL_DISTANCE && !health.is_dead() {
+            inventory.coins.add_until_full(refund(*unit_type, health));
+            commands.entity(unit).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refund_scales_with_health() {
+        let mut health = Health::from_max(10.);
+        health.apply_damage(5.);
+
+        assert_eq!(refund(UnitType::Archer, &health), 5.);
+    }
+}
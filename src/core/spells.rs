@@ -0,0 +1,228 @@
+//! Spells cast by the player with mana, independent of the units.
+
+use bevy::prelude::*;
+
+use std::fmt::Display;
+
+use super::{
+    inventory::Inventory,
+    level::Lane,
+    stats::{ArmorType, DamageType, Health},
+    status_effects::{ApplyStatusEffect, StatusEffect, StatusEffectKind},
+    Base, CoreStep, Faction, Unit,
+};
+
+/// The mana regenerated per second.
+const MANA_PER_SECOND: f32 = 1.;
+
+/// The distance from the aimed position within which units are affected by a spell.
+const SPELL_RADIUS: f32 = 50.;
+
+const METEOR_DAMAGE: f32 = 8.;
+const RALLY_FACTOR: f32 = 1.3;
+const RALLY_SECS: f32 = 8.;
+const FREEZE_SECS: f32 = 3.;
+const BASE_HEAL: f32 = 25.;
+
+pub struct SpellsPlugin;
+
+impl Plugin for SpellsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CastSpell>()
+            .add_systems(Update, mana_regeneration.in_set(CoreStep::Prepare))
+            .add_systems(Update, cast_spells.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spell {
+    /// Damage all enemies around the aimed position.
+    Meteor,
+    /// Temporarily buff all allies around the aimed position.
+    Rally,
+    /// Stun all enemies around the aimed position.
+    Freeze,
+    /// Restore health of the player base.
+    HealBase,
+}
+
+impl Spell {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Meteor, Self::Rally, Self::Freeze, Self::HealBase]
+    }
+
+    pub fn cost(&self) -> u32 {
+        match *self {
+            Self::Meteor => 30,
+            Self::Rally => 20,
+            Self::Freeze => 25,
+            Self::HealBase => 40,
+        }
+    }
+
+    /// Whether the spell has to be aimed at a position on the battlefield.
+    pub fn is_aimed(&self) -> bool {
+        !matches!(self, Self::HealBase)
+    }
+}
+
+impl Display for Spell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Meteor => "Meteor",
+            Self::Rally => "Rally",
+            Self::Freeze => "Freeze",
+            Self::HealBase => "Heal Base",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Where a spell is aimed on the battlefield.
+#[derive(Debug, Clone, Copy)]
+pub struct SpellTarget {
+    /// The position along the battlefield.
+    pub x: f32,
+    pub lane: usize,
+}
+
+/// Cast a spell, if there is enough mana to pay for it.
+#[derive(Debug, Event)]
+pub struct CastSpell {
+    pub spell: Spell,
+    /// Where the spell was aimed, `None` for spells that aren't aimed.
+    pub target: Option<SpellTarget>,
+}
+
+fn mana_regeneration(mut inventory: ResMut<Inventory>, time: Res<Time>) {
+    inventory
+        .mana
+        .add_until_full(MANA_PER_SECOND * time.delta_seconds());
+}
+
+fn cast_spells(
+    mut cast_spell_event: EventReader<CastSpell>,
+    mut inventory: ResMut<Inventory>,
+    mut apply_status_effect_event: EventWriter<ApplyStatusEffect>,
+    mut unit_query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Health,
+            Option<&ArmorType>,
+            Option<&Lane>,
+            &Faction,
+        ),
+        With<Unit>,
+    >,
+    mut base_query: Query<(&mut Health, &Faction), (With<Base>, Without<Unit>)>,
+) {
+    for CastSpell { spell, target } in cast_spell_event.read() {
+        if *spell == Spell::HealBase {
+            let Some((mut health, _)) = base_query
+                .iter_mut()
+                .find(|(_, faction)| **faction == Faction::Player)
+            else {
+                continue;
+            };
+
+            // Don't waste the mana on a base that isn't damaged
+            if health.is_injured() && inventory.mana.try_remove(spell.cost()) {
+                health.heal(BASE_HEAL);
+            }
+
+            continue;
+        }
+
+        let Some(SpellTarget { x, lane }) = *target else {
+            continue;
+        };
+
+        if !inventory.mana.try_remove(spell.cost()) {
+            continue;
+        }
+
+        for (entity, transform, mut health, armor, unit_lane, faction) in unit_query.iter_mut() {
+            // Neutral units are hit by harmful spells, but not helped by the others
+            let is_hostile = Faction::Player.is_hostile_to(*faction);
+
+            if (transform.translation.x - x).abs() > SPELL_RADIUS
+                || !Lane::meets(Some(&Lane(lane)), unit_lane)
+            {
+                continue;
+            }
+
+            match spell {
+                Spell::Meteor if is_hostile => {
+                    let armor = armor.copied().unwrap_or(ArmorType::Unarmored);
+                    health.apply_damage(METEOR_DAMAGE * DamageType::Magic.multiplier(armor));
+                }
+                Spell::Rally if !is_hostile => {
+                    apply_status_effect_event.send(ApplyStatusEffect {
+                        target: entity,
+                        effect: StatusEffect {
+                            kind: StatusEffectKind::Rally(RALLY_FACTOR),
+                            duration: RALLY_SECS,
+                        },
+                    });
+                }
+                Spell::Freeze if is_hostile => {
+                    apply_status_effect_event.send(ApplyStatusEffect {
+                        target: entity,
+                        effect: StatusEffect {
+                            kind: StatusEffectKind::Stun,
+                            duration: FREEZE_SECS,
+                        },
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::inventory::Item;
+
+    use super::*;
+
+    #[test]
+    fn healing_the_base_only_spends_mana_when_it_is_damaged() {
+        let mut app = App::new();
+        app.add_event::<CastSpell>()
+            .add_event::<ApplyStatusEffect>()
+            .insert_resource(Inventory {
+                coins: Item::new(0, 10),
+                mana: Item::new(50, 100),
+            })
+            .add_systems(Update, cast_spells);
+
+        let base = app
+            .world_mut()
+            .spawn((Base, Health::from_max(100.), Faction::Player))
+            .id();
+
+        app.world_mut().send_event(CastSpell {
+            spell: Spell::HealBase,
+            target: None,
+        });
+        app.update();
+
+        assert_eq!(app.world().resource::<Inventory>().mana.count(), 50);
+
+        app.world_mut()
+            .get_mut::<Health>(base)
+            .unwrap()
+            .apply_damage(60.);
+        app.world_mut().send_event(CastSpell {
+            spell: Spell::HealBase,
+            target: None,
+        });
+        app.update();
+
+        assert_eq!(app.world().resource::<Inventory>().mana.count(), 10);
+        assert_eq!(app.world().get::<Health>(base).unwrap().current(), 65.);
+    }
+}
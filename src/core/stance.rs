@@ -0,0 +1,144 @@
+//! Global orders that the player gives to their whole army.
+
+use bevy::prelude::*;
+
+use std::fmt::Display;
+
+use super::{hero::Hero, stats::Health, Base, CoreStep, Faction, Unit};
+
+/// The distance to the base within which retreating units heal.
+const BASE_HEAL_RADIUS: f32 = 60.;
+
+/// The health restored per second to retreating units near the base.
+const RETREAT_HEAL_PER_SECOND: f32 = 0.5;
+
+pub struct StancePlugin;
+
+impl Plugin for StancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Charge>()
+            .add_systems(Update, charge.in_set(CoreStep::Prepare))
+            .add_systems(Update, heal_at_base.in_set(CoreStep::Effects));
+    }
+}
+
+/// How the player units move, the enemy always advances.
+#[derive(Debug, Resource, Clone, Copy, PartialEq, Default)]
+pub enum ArmyStance {
+    /// March towards the enemy base.
+    #[default]
+    Advance,
+    /// Walk to the given position and hold it.
+    Hold(f32),
+    /// Walk back to the own base, without starting new fights.
+    Retreat,
+}
+
+impl ArmyStance {
+    /// Where the units walk to, `None` if they advance as far as they can.
+    pub fn destination(&self, base_x: f32) -> Option<f32> {
+        match *self {
+            Self::Advance => None,
+            Self::Hold(x) => Some(x),
+            Self::Retreat => Some(base_x),
+        }
+    }
+}
+
+impl Display for ArmyStance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Advance => "Advance",
+            Self::Hold(_) => "Hold",
+            Self::Retreat => "Retreat",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// Where newly spawned player units gather, if set.
+#[derive(Debug, Resource, Default)]
+pub struct RallyPoint(pub Option<f32>);
+
+/// A unit waiting at the rally point until an enemy comes into range.
+#[derive(Debug, Component)]
+pub struct Rallying {
+    pub x: f32,
+}
+
+/// Order all units waiting at the rally point to advance, and clear the rally point.
+#[derive(Debug, Event)]
+pub struct Charge;
+
+fn charge(
+    mut commands: Commands,
+    mut charge_event: EventReader<Charge>,
+    mut rally_point: ResMut<RallyPoint>,
+    unit_query: Query<Entity, With<Rallying>>,
+) {
+    if charge_event.read().count() == 0 {
+        return;
+    }
+
+    rally_point.0 = None;
+
+    for unit in unit_query.iter() {
+        commands.entity(unit).remove::<Rallying>();
+    }
+}
+
+fn heal_at_base(
+    stance: Res<ArmyStance>,
+    base_query: Query<(&Transform, &Faction), With<Base>>,
+    mut unit_query: Query<(&Transform, &mut Health, &Faction), (With<Unit>, Without<Hero>)>,
+    time: Res<Time>,
+) {
+    if *stance != ArmyStance::Retreat {
+        return;
+    }
+
+    let Some((base_transform, _)) = base_query
+        .iter()
+        .find(|(_, faction)| **faction == Faction::Player)
+    else {
+        return;
+    };
+
+    for (transform, mut health, faction) in unit_query.iter_mut() {
+        if *faction != Faction::Player {
+            continue;
+        }
+
+        if (transform.translation.x - base_transform.translation.x).abs() <= BASE_HEAL_RADIUS {
+            health.heal(RETREAT_HEAL_PER_SECOND * time.delta_seconds());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_advancing_units_walk_as_far_as_they_can() {
+        assert_eq!(ArmyStance::Advance.destination(-200.), None);
+        assert_eq!(ArmyStance::Hold(30.).destination(-200.), Some(30.));
+        assert_eq!(ArmyStance::Retreat.destination(-200.), Some(-200.));
+    }
+
+    #[test]
+    fn charge_releases_the_units_at_the_rally_point() {
+        let mut app = App::new();
+        app.add_event::<Charge>()
+            .insert_resource(RallyPoint(Some(40.)))
+            .add_systems(Update, charge);
+
+        let unit = app.world_mut().spawn(Rallying { x: 40. }).id();
+        app.world_mut().send_event(Charge);
+        app.update();
+
+        assert!(app.world().get::<Rallying>(unit).is_none());
+        assert_eq!(app.world().resource::<RallyPoint>().0, None);
+    }
+}
@@ -1,8 +1,14 @@
 use bevy::prelude::*;
 
-use std::fmt::Display;
+use std::{fmt::Display, ops::RangeInclusive};
 
-use super::UnitType;
+use super::{
+    status_effects::{StatusEffect, StatusEffectKind},
+    UnitType,
+};
+
+/// Attacks with a longer range than this count as ranged.
+const MELEE_RANGE: f32 = 30.;
 
 #[derive(Debug, Component, Clone)]
 pub struct MovementStats {
@@ -15,6 +21,40 @@ impl From<UnitType> for MovementStats {
             UnitType::Farmer => Self { speed: 10. },
             UnitType::Shadow => Self { speed: 10. },
             UnitType::Archer => Self { speed: 10. },
+            UnitType::Healer => Self { speed: 10. },
+            UnitType::BannerBearer => Self { speed: 10. },
+            UnitType::Catapult => Self { speed: 5. },
+            UnitType::Gryphon => Self { speed: 14. },
+            UnitType::Ram => Self { speed: 6. },
+            UnitType::Bat => Self { speed: 16. },
+            UnitType::Ghoul => Self { speed: 20. },
+            UnitType::Golem => Self { speed: 6. },
+            UnitType::Wraith => Self { speed: 8. },
+            UnitType::Splitter => Self { speed: 9. },
+            UnitType::Splitling => Self { speed: 14. },
+            UnitType::NightLord => Self { speed: 5. },
+            UnitType::Hero => Self { speed: 25. },
+            UnitType::Wolf => Self { speed: 16. },
+            UnitType::Troll => Self { speed: 6. },
+        }
+    }
+}
+
+/// Whether a unit walks on the ground or flies through the air.
+///
+/// Only attacks that can target air hit flying units.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MovementLayer {
+    #[default]
+    Ground,
+    Air,
+}
+
+impl From<UnitType> for MovementLayer {
+    fn from(unit_type: UnitType) -> Self {
+        match unit_type {
+            UnitType::Gryphon | UnitType::Bat => Self::Air,
+            _ => Self::Ground,
         }
     }
 }
@@ -30,13 +70,42 @@ impl Health {
         Self { current: max, max }
     }
 
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
     pub fn is_dead(&self) -> bool {
         self.current <= 0.0
     }
 
+    pub fn is_injured(&self) -> bool {
+        self.current < self.max
+    }
+
     pub fn apply_damage(&mut self, damage: f32) {
         self.current -= damage;
     }
+
+    /// Restore health, without exceeding the maximum.
+    ///
+    /// The dead can't be healed back to life.
+    pub fn heal(&mut self, amount: f32) {
+        if self.is_dead() {
+            return;
+        }
+
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    /// Raise the maximum health, restoring the added health as well.
+    pub fn increase_max(&mut self, amount: f32) {
+        self.max += amount;
+        self.current += amount;
+    }
 }
 
 impl From<UnitType> for Health {
@@ -45,6 +114,21 @@ impl From<UnitType> for Health {
             UnitType::Farmer => 5.,
             UnitType::Shadow => 10.,
             UnitType::Archer => 2.,
+            UnitType::Healer => 3.,
+            UnitType::BannerBearer => 6.,
+            UnitType::Catapult => 8.,
+            UnitType::Gryphon => 6.,
+            UnitType::Ram => 25.,
+            UnitType::Bat => 3.,
+            UnitType::Ghoul => 5.,
+            UnitType::Golem => 30.,
+            UnitType::Wraith => 6.,
+            UnitType::Splitter => 12.,
+            UnitType::Splitling => 3.,
+            UnitType::NightLord => 150.,
+            UnitType::Hero => 40.,
+            UnitType::Wolf => 6.,
+            UnitType::Troll => 20.,
         };
 
         Self::from_max(max)
@@ -60,26 +144,485 @@ impl Display for Health {
 #[derive(Debug, Component, Clone)]
 pub struct AttackStats {
     pub attack_range: f32,
+    /// Enemies closer than this can't be attacked.
+    pub min_attack_range: f32,
     pub attack_damage: f32,
+    pub damage_type: DamageType,
+    /// The status effect applied to the target on hit.
+    pub status_effect: Option<StatusEffect>,
+    pub area: AttackArea,
+    /// The fraction of the damage lost at the edge of the area.
+    pub falloff: f32,
+    /// The maximum number of units hit by a single attack.
+    pub max_targets: Option<usize>,
+    /// The factor by which the attack animation is sped up.
+    pub attack_speed: f32,
+    /// The duration of the wind-up before the attack hits, in seconds.
+    pub foreswing: f32,
+    /// The duration of the recovery after the attack hit, in seconds.
+    pub backswing: f32,
+    /// Stop the attack if the target dies before it hits.
+    pub cancel_on_target_death: bool,
+    /// Whether flying units can be hit.
+    pub can_target_air: bool,
+}
+
+impl AttackStats {
+    /// The stats with the given modifiers applied.
+    pub fn with_modifiers(&self, modifiers: &StatModifiers) -> Self {
+        Self {
+            attack_range: self.attack_range * modifiers.attack_range,
+            attack_damage: self.attack_damage * modifiers.attack_damage,
+            attack_speed: self.attack_speed * modifiers.attack_speed,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the attack hits from afar, rather than in melee.
+    pub fn is_ranged(&self) -> bool {
+        self.attack_range > MELEE_RANGE
+    }
+
+    /// The distances at which enemies can be attacked.
+    pub fn range(&self) -> RangeInclusive<f32> {
+        self.min_attack_range..=self.attack_range
+    }
+
+    pub fn foreswing_secs(&self) -> f32 {
+        self.foreswing / self.attack_speed
+    }
+
+    pub fn backswing_secs(&self) -> f32 {
+        self.backswing / self.attack_speed
+    }
+
+    /// The damage dealt to a target with the given armor.
+    pub fn damage_against(&self, armor: ArmorType) -> f32 {
+        self.attack_damage * self.damage_type.multiplier(armor)
+    }
+}
+
+impl Default for AttackStats {
+    /// A basic melee attack.
+    fn default() -> Self {
+        Self {
+            attack_range: 25.,
+            min_attack_range: 0.,
+            attack_damage: 2.,
+            damage_type: DamageType::Slash,
+            status_effect: None,
+            area: AttackArea::Single,
+            falloff: 0.,
+            max_targets: None,
+            attack_speed: 1.,
+            foreswing: 1.,
+            backswing: 0.5,
+            cancel_on_target_death: false,
+            can_target_air: false,
+        }
+    }
 }
 
 impl From<UnitType> for AttackStats {
     fn from(value: UnitType) -> Self {
         match value {
             UnitType::Farmer => Self {
-                attack_range: 25.,
-                attack_damage: 2.,
+                foreswing: 0.8,
+                backswing: 0.4,
+                ..default()
             },
 
             UnitType::Shadow => Self {
                 attack_range: 20.,
-                attack_damage: 2.,
+                damage_type: DamageType::Magic,
+                status_effect: Some(StatusEffect {
+                    kind: StatusEffectKind::Slow(0.7),
+                    duration: 2.,
+                }),
+                ..default()
             },
 
             UnitType::Archer => Self {
                 attack_range: 100.,
-                attack_damage: 2.,
+                damage_type: DamageType::Pierce,
+                foreswing: 1.2,
+                backswing: 0.3,
+                cancel_on_target_death: true,
+                can_target_air: true,
+                ..default()
+            },
+
+            // The healer doesn't attack, its "attacks" heal allies instead
+            UnitType::Healer => Self {
+                attack_range: 60.,
+                attack_damage: 0.,
+                damage_type: DamageType::Magic,
+                backswing: 1.,
+                cancel_on_target_death: true,
+                ..default()
+            },
+
+            UnitType::BannerBearer => Self {
+                attack_damage: 1.,
+                ..default()
+            },
+
+            UnitType::Catapult => Self {
+                attack_range: 150.,
+                min_attack_range: 50.,
+                attack_damage: 10.,
+                damage_type: DamageType::Siege,
+                area: AttackArea::Splash { radius: 20. },
+                falloff: 0.5,
+                foreswing: 2.,
+                backswing: 2.,
+                ..default()
+            },
+
+            UnitType::Gryphon => Self {
+                attack_damage: 2.5,
+                ..default()
+            },
+
+            UnitType::Ram => Self {
+                attack_range: 20.,
+                attack_damage: 8.,
+                damage_type: DamageType::Siege,
+                foreswing: 1.5,
+                backswing: 1.5,
+                ..default()
+            },
+
+            UnitType::Bat => Self {
+                attack_range: 20.,
+                attack_damage: 1.,
+                damage_type: DamageType::Pierce,
+                foreswing: 0.6,
+                backswing: 0.3,
+                ..default()
+            },
+
+            UnitType::Ghoul => Self {
+                attack_range: 20.,
+                attack_damage: 1.5,
+                foreswing: 0.6,
+                backswing: 0.3,
+                ..default()
+            },
+
+            UnitType::Golem => Self {
+                attack_damage: 4.,
+                foreswing: 1.5,
+                backswing: 1.,
+                ..default()
+            },
+
+            UnitType::Wraith => Self {
+                attack_range: 80.,
+                attack_damage: 1.5,
+                damage_type: DamageType::Magic,
+                status_effect: Some(StatusEffect {
+                    kind: StatusEffectKind::Poison(0.5),
+                    duration: 4.,
+                }),
+                cancel_on_target_death: true,
+                can_target_air: true,
+                ..default()
+            },
+
+            UnitType::Splitter => Self {
+                attack_range: 20.,
+                ..default()
+            },
+
+            UnitType::Splitling => Self {
+                attack_range: 15.,
+                attack_damage: 1.,
+                foreswing: 0.6,
+                backswing: 0.3,
+                ..default()
+            },
+
+            UnitType::NightLord => Self {
+                attack_range: 40.,
+                attack_damage: 5.,
+                area: AttackArea::Cone { half_angle: 0.6 },
+                falloff: 0.3,
+                foreswing: 1.5,
+                backswing: 1.,
+                ..default()
+            },
+
+            UnitType::Hero => Self {
+                attack_damage: 3.,
+                foreswing: 0.5,
+                backswing: 0.3,
+                ..default()
             },
+
+            UnitType::Wolf => Self {
+                attack_range: 20.,
+                attack_damage: 1.5,
+                foreswing: 0.5,
+                backswing: 0.3,
+                ..default()
+            },
+
+            UnitType::Troll => Self {
+                attack_damage: 3.,
+                foreswing: 1.,
+                backswing: 0.8,
+                ..default()
+            },
+        }
+    }
+}
+
+/// Reduces all damage taken by the given fraction, e.g. from armor upgrades of a base.
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct DamageReduction(pub f32);
+
+impl DamageReduction {
+    pub fn apply(&self, damage: f32) -> f32 {
+        damage * (1. - self.0).clamp(0., 1.)
+    }
+}
+
+/// Factors applied to the stats of a unit, e.g. by status effects.
+///
+/// They are reset every frame and then accumulated from all active sources.
+#[derive(Debug, Component, Clone)]
+pub struct StatModifiers {
+    pub speed: f32,
+    pub attack_range: f32,
+    pub attack_damage: f32,
+    pub attack_speed: f32,
+}
+
+impl Default for StatModifiers {
+    fn default() -> Self {
+        Self {
+            speed: 1.,
+            attack_range: 1.,
+            attack_damage: 1.,
+            attack_speed: 1.,
+        }
+    }
+}
+
+/// The area hit by an attack, in addition to its target.
+#[derive(Debug, Clone, Copy)]
+pub enum AttackArea {
+    /// Only the target is hit.
+    Single,
+    /// All enemies within the radius around the target are hit.
+    Splash { radius: f32 },
+    /// All enemies within the attack range and the given angle (in radians)
+    /// to both sides of the attack direction are hit.
+    Cone { half_angle: f32 },
+    /// All enemies within the attack range on a line of the given width are hit.
+    Line { width: f32 },
+}
+
+impl AttackArea {
+    /// How far the position is from the center of the area, from `0.` at the center to `1.` at the edge.
+    ///
+    /// Returns `None` if the position is outside of the area.
+    pub fn coverage(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        range: f32,
+        target: Vec2,
+        position: Vec2,
+    ) -> Option<f32> {
+        match *self {
+            Self::Single => None,
+            Self::Splash { radius } => {
+                let distance = position.distance(target);
+                (distance <= radius).then_some(distance / radius)
+            }
+            Self::Cone { half_angle } => {
+                let offset = position - origin;
+                let distance = offset.length();
+
+                if distance == 0. {
+                    return Some(0.);
+                }
+
+                (distance <= range && offset.angle_between(direction).abs() <= half_angle)
+                    .then_some(distance / range)
+            }
+            Self::Line { width } => {
+                let offset = position - origin;
+                let along = offset.dot(direction);
+                let across = (offset - direction * along).length();
+
+                (along >= 0. && along <= range && across <= width / 2.).then_some(along / range)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageType {
+    Pierce,
+    Slash,
+    Magic,
+    Siege,
+}
+
+impl DamageType {
+    /// The factor by which damage of this type is scaled against the given armor.
+    pub fn multiplier(&self, armor: ArmorType) -> f32 {
+        match (*self, armor) {
+            (Self::Pierce, ArmorType::Unarmored) => 1.,
+            (Self::Pierce, ArmorType::Light) => 1.25,
+            (Self::Pierce, ArmorType::Heavy) => 0.5,
+            (Self::Pierce, ArmorType::Fortified) => 0.35,
+            (Self::Pierce, ArmorType::Spectral) => 1.5,
+
+            (Self::Slash, ArmorType::Unarmored) => 1.,
+            (Self::Slash, ArmorType::Light) => 1.,
+            (Self::Slash, ArmorType::Heavy) => 0.75,
+            (Self::Slash, ArmorType::Fortified) => 0.5,
+            (Self::Slash, ArmorType::Spectral) => 0.75,
+
+            (Self::Magic, ArmorType::Unarmored) => 1.,
+            (Self::Magic, ArmorType::Light) => 1.,
+            (Self::Magic, ArmorType::Heavy) => 1.5,
+            (Self::Magic, ArmorType::Fortified) => 0.5,
+            (Self::Magic, ArmorType::Spectral) => 1.25,
+
+            (Self::Siege, ArmorType::Unarmored) => 1.,
+            (Self::Siege, ArmorType::Light) => 0.75,
+            (Self::Siege, ArmorType::Heavy) => 1.,
+            (Self::Siege, ArmorType::Fortified) => 2.,
+            (Self::Siege, ArmorType::Spectral) => 0.5,
+        }
+    }
+}
+
+/// The armor of a unit or base, reducing or amplifying the damage it takes.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum ArmorType {
+    Unarmored,
+    Light,
+    Heavy,
+    Fortified,
+    Spectral,
+}
+
+impl From<UnitType> for ArmorType {
+    fn from(value: UnitType) -> Self {
+        match value {
+            UnitType::Farmer => Self::Unarmored,
+            UnitType::Shadow => Self::Spectral,
+            UnitType::Archer => Self::Light,
+            UnitType::Healer => Self::Unarmored,
+            UnitType::BannerBearer => Self::Light,
+            UnitType::Catapult => Self::Heavy,
+            UnitType::Gryphon => Self::Light,
+            UnitType::Ram => Self::Heavy,
+            UnitType::Bat => Self::Unarmored,
+            UnitType::Ghoul => Self::Unarmored,
+            UnitType::Golem => Self::Heavy,
+            UnitType::Wraith => Self::Spectral,
+            UnitType::Splitter => Self::Light,
+            UnitType::Splitling => Self::Unarmored,
+            UnitType::NightLord => Self::Heavy,
+            UnitType::Hero => Self::Heavy,
+            UnitType::Wolf => Self::Light,
+            UnitType::Troll => Self::Heavy,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heal_is_capped_at_max_health() {
+        let mut health = Health::from_max(10.);
+        health.apply_damage(3.);
+
+        health.heal(5.);
+
+        assert_eq!(health.current(), 10.);
+        assert!(!health.is_injured());
+    }
+
+    #[test]
+    fn heal_does_not_revive_the_dead() {
+        let mut health = Health::from_max(10.);
+        health.apply_damage(10.);
+
+        health.heal(5.);
+
+        assert!(health.is_dead());
+    }
+
+    #[test]
+    fn splash_covers_units_around_the_target() {
+        let area = AttackArea::Splash { radius: 10. };
+
+        let coverage = |x: f32| {
+            area.coverage(
+                Vec2::ZERO,
+                Vec2::X,
+                100.,
+                Vec2::new(50., 0.),
+                Vec2::new(x, 0.),
+            )
+        };
+
+        assert_eq!(coverage(50.), Some(0.));
+        assert_eq!(coverage(55.), Some(0.5));
+        assert_eq!(coverage(70.), None);
+    }
+
+    #[test]
+    fn cones_cover_units_in_front_within_the_angle() {
+        let area = AttackArea::Cone { half_angle: 0.5 };
+
+        let coverage =
+            |position: Vec2| area.coverage(Vec2::ZERO, Vec2::X, 40., Vec2::new(20., 0.), position);
+
+        assert_eq!(coverage(Vec2::new(20., 0.)), Some(0.5));
+        assert!(coverage(Vec2::new(20., 5.)).is_some());
+        assert_eq!(coverage(Vec2::new(5., 20.)), None);
+        assert_eq!(coverage(Vec2::new(-20., 0.)), None);
+        assert_eq!(coverage(Vec2::new(60., 0.)), None);
+    }
+
+    #[test]
+    fn lines_cover_units_within_their_width() {
+        let area = AttackArea::Line { width: 10. };
+
+        let coverage =
+            |position: Vec2| area.coverage(Vec2::ZERO, Vec2::X, 100., Vec2::new(20., 0.), position);
+
+        assert_eq!(coverage(Vec2::new(50., 4.)), Some(0.5));
+        assert_eq!(coverage(Vec2::new(50., 6.)), None);
+        assert_eq!(coverage(Vec2::new(120., 0.)), None);
+    }
+
+    #[test]
+    fn archers_are_strong_against_shadows() {
+        let archer = AttackStats::from(UnitType::Archer);
+
+        let damage = archer.damage_against(ArmorType::from(UnitType::Shadow));
+
+        assert!(damage > archer.attack_damage);
+    }
+
+    #[test]
+    fn pierce_is_weak_against_heavy_armor() {
+        let archer = AttackStats::from(UnitType::Archer);
+
+        let damage = archer.damage_against(ArmorType::Heavy);
+
+        assert!(damage < archer.attack_damage);
+    }
+}
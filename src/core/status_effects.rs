@@ -0,0 +1,466 @@
+//! Timed effects that attacks and spells can apply to their targets.
+
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{
+    stats::{DamageReduction, Health, StatModifiers},
+    CoreStep,
+};
+
+/// The maximum number of poison applications that stack on a single unit.
+const MAX_POISON_STACKS: u32 = 5;
+
+pub struct StatusEffectsPlugin;
+
+impl Plugin for StatusEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatusEffect>()
+            .add_systems(
+                Update,
+                (apply_slow, apply_rally).in_set(CoreStep::Modifiers),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_status_effects,
+                    damage_over_time,
+                    (
+                        expire_status_effect::<Slowed>,
+                        expire_status_effect::<Poisoned>,
+                        expire_status_effect::<Burning>,
+                        expire_status_effect::<Stunned>,
+                        expire_status_effect::<Rallied>,
+                    ),
+                )
+                    .chain()
+                    .in_set(CoreStep::Effects),
+            );
+    }
+}
+
+/// An effect that an attack applies to its target.
+#[derive(Debug, Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: StatusEffectKind,
+    /// The duration of the effect in seconds.
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StatusEffectKind {
+    /// Multiply the movement speed with the given factor.
+    ///
+    /// Only the strongest slow is active at a time.
+    Slow(f32),
+    /// Deal the given damage per second.
+    ///
+    /// Stacks up to [`MAX_POISON_STACKS`] times.
+    Poison(f32),
+    /// Deal the given damage per second.
+    ///
+    /// Doesn't stack, but new applications refresh the duration.
+    Burn(f32),
+    /// Prevent the unit from moving and from starting new attacks.
+    Stun,
+    /// Multiply the movement speed and attack damage with the given factor.
+    ///
+    /// Only the strongest rally is active at a time.
+    Rally(f32),
+}
+
+#[derive(Debug, Event)]
+pub struct ApplyStatusEffect {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+#[derive(Debug, Component)]
+pub struct Slowed {
+    pub factor: f32,
+    timer: Timer,
+}
+
+#[derive(Debug, Component)]
+pub struct Poisoned {
+    pub stacks: u32,
+    pub damage_per_second: f32,
+    timer: Timer,
+}
+
+#[derive(Debug, Component)]
+pub struct Burning {
+    pub damage_per_second: f32,
+    timer: Timer,
+}
+
+#[derive(Debug, Component)]
+pub struct Stunned {
+    timer: Timer,
+}
+
+#[derive(Debug, Component)]
+pub struct Rallied {
+    pub factor: f32,
+    timer: Timer,
+}
+
+/// A status effect that wears off after some time.
+trait TimedStatus {
+    fn timer_mut(&mut self) -> &mut Timer;
+}
+
+impl TimedStatus for Slowed {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl TimedStatus for Poisoned {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl TimedStatus for Burning {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl TimedStatus for Stunned {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl TimedStatus for Rallied {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+/// Restart the timer, unless more time than the new duration is left.
+fn refresh(timer: &mut Timer, duration: f32) {
+    if timer.remaining_secs() < duration {
+        *timer = Timer::from_seconds(duration, TimerMode::Once);
+    }
+}
+
+impl Slowed {
+    fn new(factor: f32, duration: f32) -> Self {
+        Self {
+            factor,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    fn stack(&mut self, factor: f32, duration: f32) {
+        if factor < self.factor {
+            // A stronger slow replaces the current one
+            *self = Self::new(factor, duration);
+        } else if factor == self.factor {
+            refresh(&mut self.timer, duration);
+        }
+    }
+}
+
+impl Poisoned {
+    fn new(damage_per_second: f32, duration: f32) -> Self {
+        Self {
+            stacks: 1,
+            damage_per_second,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    fn stack(&mut self, damage_per_second: f32, duration: f32) {
+        self.stacks = (self.stacks + 1).min(MAX_POISON_STACKS);
+        self.damage_per_second = self.damage_per_second.max(damage_per_second);
+        refresh(&mut self.timer, duration);
+    }
+}
+
+impl Burning {
+    fn new(damage_per_second: f32, duration: f32) -> Self {
+        Self {
+            damage_per_second,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    fn stack(&mut self, damage_per_second: f32, duration: f32) {
+        self.damage_per_second = self.damage_per_second.max(damage_per_second);
+        refresh(&mut self.timer, duration);
+    }
+}
+
+impl Stunned {
+    fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    fn stack(&mut self, duration: f32) {
+        refresh(&mut self.timer, duration);
+    }
+}
+
+impl Rallied {
+    fn new(factor: f32, duration: f32) -> Self {
+        Self {
+            factor,
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+
+    fn stack(&mut self, factor: f32, duration: f32) {
+        if factor > self.factor {
+            // A stronger rally replaces the current one
+            *self = Self::new(factor, duration);
+        } else if factor == self.factor {
+            refresh(&mut self.timer, duration);
+        }
+    }
+}
+
+/// The status effects that a unit gains this frame, inserted once all events are read.
+///
+/// Later events in the same frame stack onto these instead of replacing them.
+#[derive(Default)]
+struct NewStatusEffects {
+    slowed: Option<Slowed>,
+    poisoned: Option<Poisoned>,
+    burning: Option<Burning>,
+    stunned: Option<Stunned>,
+    rallied: Option<Rallied>,
+}
+
+fn apply_status_effects(
+    mut commands: Commands,
+    mut apply_status_effect_event: EventReader<ApplyStatusEffect>,
+    mut target_query: Query<(
+        Option<&mut Slowed>,
+        Option<&mut Poisoned>,
+        Option<&mut Burning>,
+        Option<&mut Stunned>,
+        Option<&mut Rallied>,
+    )>,
+) {
+    let mut new_effects: HashMap<Entity, NewStatusEffects> = HashMap::default();
+
+    for ApplyStatusEffect { target, effect } in apply_status_effect_event.read() {
+        let Ok((mut slowed, mut poisoned, mut burning, mut stunned, mut rallied)) =
+            target_query.get_mut(*target)
+        else {
+            continue;
+        };
+        let new = new_effects.entry(*target).or_default();
+        let duration = effect.duration;
+
+        match effect.kind {
+            StatusEffectKind::Slow(factor) => match slowed.as_deref_mut().or(new.slowed.as_mut()) {
+                Some(slowed) => slowed.stack(factor, duration),
+                None => new.slowed = Some(Slowed::new(factor, duration)),
+            },
+            StatusEffectKind::Poison(damage_per_second) => {
+                match poisoned.as_deref_mut().or(new.poisoned.as_mut()) {
+                    Some(poisoned) => poisoned.stack(damage_per_second, duration),
+                    None => new.poisoned = Some(Poisoned::new(damage_per_second, duration)),
+                }
+            }
+            StatusEffectKind::Burn(damage_per_second) => {
+                match burning.as_deref_mut().or(new.burning.as_mut()) {
+                    Some(burning) => burning.stack(damage_per_second, duration),
+                    None => new.burning = Some(Burning::new(damage_per_second, duration)),
+                }
+            }
+            StatusEffectKind::Stun => match stunned.as_deref_mut().or(new.stunned.as_mut()) {
+                Some(stunned) => stunned.stack(duration),
+                None => new.stunned = Some(Stunned::new(duration)),
+            },
+            StatusEffectKind::Rally(factor) => {
+                match rallied.as_deref_mut().or(new.rallied.as_mut()) {
+                    Some(rallied) => rallied.stack(factor, duration),
+                    None => new.rallied = Some(Rallied::new(factor, duration)),
+                }
+            }
+        }
+    }
+
+    for (target, new) in new_effects {
+        let mut target = commands.entity(target);
+
+        if let Some(slowed) = new.slowed {
+            target.try_insert(slowed);
+        }
+        if let Some(poisoned) = new.poisoned {
+            target.try_insert(poisoned);
+        }
+        if let Some(burning) = new.burning {
+            target.try_insert(burning);
+        }
+        if let Some(stunned) = new.stunned {
+            target.try_insert(stunned);
+        }
+        if let Some(rallied) = new.rallied {
+            target.try_insert(rallied);
+        }
+    }
+}
+
+fn apply_slow(mut unit_query: Query<(&mut StatModifiers, &Slowed)>) {
+    for (mut modifiers, slowed) in unit_query.iter_mut() {
+        modifiers.speed *= slowed.factor;
+    }
+}
+
+fn apply_rally(mut unit_query: Query<(&mut StatModifiers, &Rallied)>) {
+    for (mut modifiers, rallied) in unit_query.iter_mut() {
+        modifiers.speed *= rallied.factor;
+        modifiers.attack_damage *= rallied.factor;
+    }
+}
+
+fn damage_over_time(
+    mut unit_query: Query<
+        (
+            &mut Health,
+            Option<&Poisoned>,
+            Option<&Burning>,
+            Option<&DamageReduction>,
+        ),
+        Or<(With<Poisoned>, With<Burning>)>,
+    >,
+    time: Res<Time>,
+) {
+    for (mut health, poisoned, burning, reduction) in unit_query.iter_mut() {
+        let poison_damage = poisoned
+            .map(|poisoned| poisoned.stacks as f32 * poisoned.damage_per_second)
+            .unwrap_or(0.);
+        let burn_damage = burning
+            .map(|burning| burning.damage_per_second)
+            .unwrap_or(0.);
+
+        let damage = (poison_damage + burn_damage) * time.delta_seconds();
+
+        // Armor upgrades of the base protect against damage over time as well
+        health.apply_damage(reduction.map_or(damage, |reduction| reduction.apply(damage)));
+    }
+}
+
+fn expire_status_effect<T: Component + TimedStatus>(
+    mut commands: Commands,
+    mut unit_query: Query<(Entity, &mut T)>,
+    time: Res<Time>,
+) {
+    for (entity, mut status) in unit_query.iter_mut() {
+        if status.timer_mut().tick(time.delta()).finished() {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn only_the_strongest_slow_is_active() {
+        let mut slowed = Slowed::new(0.5, 1.);
+
+        slowed.stack(0.8, 5.);
+        assert_eq!(slowed.factor, 0.5);
+        assert_eq!(slowed.timer.remaining_secs(), 1.);
+
+        slowed.stack(0.3, 2.);
+        assert_eq!(slowed.factor, 0.3);
+        assert_eq!(slowed.timer.remaining_secs(), 2.);
+    }
+
+    #[test]
+    fn poison_stacks_up_to_the_maximum() {
+        let mut poisoned = Poisoned::new(1., 3.);
+
+        for _ in 0..MAX_POISON_STACKS + 2 {
+            poisoned.stack(2., 3.);
+        }
+
+        assert_eq!(poisoned.stacks, MAX_POISON_STACKS);
+        assert_eq!(poisoned.damage_per_second, 2.);
+    }
+
+    #[test]
+    fn burns_and_stuns_refresh_without_stacking() {
+        let mut burning = Burning::new(4., 1.);
+        burning.stack(2., 3.);
+
+        assert_eq!(burning.damage_per_second, 4.);
+        assert_eq!(burning.timer.remaining_secs(), 3.);
+
+        let mut stunned = Stunned::new(2.);
+        stunned.stack(1.);
+
+        assert_eq!(stunned.timer.remaining_secs(), 2.);
+    }
+
+    #[test]
+    fn effects_applied_in_the_same_frame_stack() {
+        let mut app = App::new();
+        app.add_event::<ApplyStatusEffect>()
+            .add_systems(Update, apply_status_effects);
+        let target = app.world_mut().spawn_empty().id();
+
+        for damage_per_second in [1., 2., 3.] {
+            app.world_mut().send_event(ApplyStatusEffect {
+                target,
+                effect: StatusEffect {
+                    kind: StatusEffectKind::Poison(damage_per_second),
+                    duration: 2.,
+                },
+            });
+        }
+        app.world_mut().send_event(ApplyStatusEffect {
+            target,
+            effect: StatusEffect {
+                kind: StatusEffectKind::Slow(0.5),
+                duration: 1.,
+            },
+        });
+        app.update();
+
+        let poisoned = app.world().get::<Poisoned>(target).unwrap();
+        assert_eq!(poisoned.stacks, 3);
+        assert_eq!(poisoned.damage_per_second, 3.);
+        assert_eq!(app.world().get::<Slowed>(target).unwrap().factor, 0.5);
+    }
+
+    #[test]
+    fn damage_reduction_protects_against_damage_over_time() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .add_systems(Update, damage_over_time);
+        let unit = app
+            .world_mut()
+            .spawn((Health::from_max(100.), Poisoned::new(4., 5.)))
+            .id();
+        let base = app
+            .world_mut()
+            .spawn((
+                Health::from_max(100.),
+                Poisoned::new(4., 5.),
+                DamageReduction(0.5),
+            ))
+            .id();
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(unit).unwrap().current(), 96.);
+        assert_eq!(app.world().get::<Health>(base).unwrap().current(), 98.);
+    }
+}
@@ -0,0 +1,163 @@
+//! Units that support their allies instead of fighting.
+
+use bevy::prelude::*;
+
+use super::{
+    level::Lane,
+    stats::{Health, StatModifiers},
+    CoreStep, Faction, UnitType,
+};
+
+pub struct SupportPlugin;
+
+impl Plugin for SupportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Heal>()
+            .add_systems(Update, apply_auras.in_set(CoreStep::Modifiers))
+            .add_systems(Update, heal.in_set(CoreStep::Effects));
+    }
+}
+
+/// Heals allies in its attack range instead of attacking enemies.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct Healer {
+    /// The health restored with each "attack".
+    pub amount: f32,
+}
+
+impl Healer {
+    pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+        match unit_type {
+            UnitType::Healer => Some(Self { amount: 2. }),
+            _ => None,
+        }
+    }
+}
+
+/// Buffs the stats of all allies within the radius.
+///
+/// Auras don't stack, only the strongest buff of each stat is applied.
+#[derive(Debug, Component, Clone)]
+pub struct Aura {
+    pub radius: f32,
+    pub modifiers: StatModifiers,
+}
+
+impl Aura {
+    pub fn for_unit(unit_type: UnitType) -> Option<Self> {
+        match unit_type {
+            UnitType::BannerBearer => Some(Self {
+                radius: 60.,
+                modifiers: StatModifiers {
+                    speed: 1.1,
+                    attack_damage: 1.25,
+                    ..default()
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Event)]
+pub struct Heal {
+    pub target: Entity,
+    pub amount: f32,
+}
+
+fn heal(mut heal_event: EventReader<Heal>, mut target_query: Query<&mut Health>) {
+    for Heal { target, amount } in heal_event.read() {
+        if let Ok(mut health) = target_query.get_mut(*target) {
+            health.heal(*amount);
+        }
+    }
+}
+
+/// The strongest buff of each stat among the auras.
+fn strongest_buff<'a>(auras: impl IntoIterator<Item = &'a Aura>) -> StatModifiers {
+    auras
+        .into_iter()
+        .fold(StatModifiers::default(), |strongest, aura| StatModifiers {
+            speed: strongest.speed.max(aura.modifiers.speed),
+            attack_range: strongest.attack_range.max(aura.modifiers.attack_range),
+            attack_damage: strongest.attack_damage.max(aura.modifiers.attack_damage),
+            attack_speed: strongest.attack_speed.max(aura.modifiers.attack_speed),
+        })
+}
+
+fn apply_auras(
+    aura_query: Query<(Entity, &Transform, &Aura, Option<&Lane>, &Faction)>,
+    mut unit_query: Query<(
+        Entity,
+        &Transform,
+        &mut StatModifiers,
+        Option<&Lane>,
+        &Faction,
+    )>,
+) {
+    for (entity, transform, mut modifiers, lane, faction) in unit_query.iter_mut() {
+        let auras = aura_query
+            .iter()
+            // The bearer of an aura only buffs the allies around it
+            .filter(
+                |(aura_entity, aura_transform, aura, aura_lane, aura_faction)| {
+                    let distance = (aura_transform.translation.x - transform.translation.x).abs();
+
+                    *aura_entity != entity
+                        && faction == *aura_faction
+                        && distance <= aura.radius
+                        && Lane::meets(lane, *aura_lane)
+                },
+            )
+            .map(|(_, _, aura, _, _)| aura);
+        let strongest = strongest_buff(auras);
+
+        modifiers.speed *= strongest.speed;
+        modifiers.attack_range *= strongest.attack_range;
+        modifiers.attack_damage *= strongest.attack_damage;
+        modifiers.attack_speed *= strongest.attack_speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aura(speed: f32, attack_damage: f32) -> Aura {
+        Aura {
+            radius: 50.,
+            modifiers: StatModifiers {
+                speed,
+                attack_damage,
+                ..default()
+            },
+        }
+    }
+
+    #[test]
+    fn auras_do_not_stack() {
+        let banner = Aura::for_unit(UnitType::BannerBearer).unwrap();
+
+        let buff = strongest_buff([&banner, &banner]);
+
+        assert_eq!(buff.attack_damage, banner.modifiers.attack_damage);
+        assert_eq!(buff.speed, banner.modifiers.speed);
+    }
+
+    #[test]
+    fn the_strongest_buff_of_each_stat_applies() {
+        let buff = strongest_buff([&aura(1.5, 1.1), &aura(1.2, 1.3)]);
+
+        assert_eq!(buff.speed, 1.5);
+        assert_eq!(buff.attack_damage, 1.3);
+        assert_eq!(buff.attack_range, 1.);
+    }
+
+    #[test]
+    fn without_auras_nothing_changes() {
+        let buff = strongest_buff([]);
+
+        assert_eq!(buff.speed, 1.);
+        assert_eq!(buff.attack_damage, 1.);
+    }
+}
@@ -0,0 +1,251 @@
+//! Choosing which enemy a unit attacks.
+
+use bevy::prelude::*;
+
+use std::{cmp::Ordering, fmt::Display, ops::RangeInclusive};
+
+use super::{
+    stats::{AttackStats, Health, MovementLayer},
+    Faction, UnitType,
+};
+
+/// How a unit picks its target among the enemies in range.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum TargetingPolicy {
+    Closest,
+    LowestHealth,
+    HighestThreat,
+    BasesOnly,
+    IgnoreBases,
+}
+
+impl TargetingPolicy {
+    /// Select the target among the candidates in front of the origin and within the range.
+    pub fn select(
+        &self,
+        origin: Vec3,
+        direction: f32,
+        range: RangeInclusive<f32>,
+        candidates: impl IntoIterator<Item = TargetCandidate>,
+    ) -> Option<TargetCandidate> {
+        let distance = |candidate: &TargetCandidate| (candidate.translation.x - origin.x).abs();
+
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.is_in_range(origin, direction, &range))
+            .filter(|candidate| match self {
+                Self::BasesOnly => candidate.is_base,
+                Self::IgnoreBases => !candidate.is_base,
+                _ => true,
+            })
+            .min_by(|a, b| {
+                let ordering = match self {
+                    Self::LowestHealth => a.health.total_cmp(&b.health),
+                    Self::HighestThreat => b.threat.total_cmp(&a.threat),
+                    _ => Ordering::Equal,
+                };
+
+                // Prefer closer targets if the policy doesn't decide
+                ordering.then_with(|| distance(a).total_cmp(&distance(b)))
+            })
+    }
+}
+
+impl From<UnitType> for TargetingPolicy {
+    fn from(value: UnitType) -> Self {
+        match value {
+            UnitType::Farmer => Self::Closest,
+            UnitType::Shadow => Self::Closest,
+            UnitType::Archer => Self::LowestHealth,
+            UnitType::Healer => Self::Closest,
+            UnitType::BannerBearer => Self::Closest,
+            UnitType::Catapult => Self::BasesOnly,
+            UnitType::Gryphon => Self::Closest,
+            UnitType::Ram => Self::BasesOnly,
+            UnitType::Bat => Self::Closest,
+            UnitType::Ghoul => Self::LowestHealth,
+            UnitType::Golem => Self::Closest,
+            UnitType::Wraith => Self::HighestThreat,
+            UnitType::Splitter => Self::Closest,
+            UnitType::Splitling => Self::Closest,
+            UnitType::NightLord => Self::Closest,
+            UnitType::Hero => Self::Closest,
+            UnitType::Wolf => Self::Closest,
+            UnitType::Troll => Self::Closest,
+        }
+    }
+}
+
+impl Display for TargetingPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match *self {
+            Self::Closest => "Closest",
+            Self::LowestHealth => "Lowest health",
+            Self::HighestThreat => "Highest threat",
+            Self::BasesOnly => "Bases only",
+            Self::IgnoreBases => "Ignore bases",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// The targeting policy that the player chose for all of their units.
+///
+/// If it's not set, each unit uses its own policy.
+#[derive(Debug, Resource, Default)]
+pub struct PlayerTargeting(pub Option<TargetingPolicy>);
+
+impl PlayerTargeting {
+    /// The policy used by a unit with the given policy of its own.
+    ///
+    /// Siege units always stick to attacking bases.
+    pub fn policy_for(&self, policy: TargetingPolicy, faction: Faction) -> TargetingPolicy {
+        if faction != Faction::Player || policy == TargetingPolicy::BasesOnly {
+            policy
+        } else {
+            self.0.unwrap_or(policy)
+        }
+    }
+
+    /// Switch to the next policy, going back to the unit policies after the last one.
+    pub fn cycle(&mut self) {
+        self.0 = match self.0 {
+            None => Some(TargetingPolicy::Closest),
+            Some(TargetingPolicy::Closest) => Some(TargetingPolicy::LowestHealth),
+            Some(TargetingPolicy::LowestHealth) => Some(TargetingPolicy::HighestThreat),
+            Some(TargetingPolicy::HighestThreat) => Some(TargetingPolicy::BasesOnly),
+            Some(TargetingPolicy::BasesOnly) => Some(TargetingPolicy::IgnoreBases),
+            Some(TargetingPolicy::IgnoreBases) => None,
+        };
+    }
+}
+
+impl Display for PlayerTargeting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(policy) => write!(f, "{policy}"),
+            None => write!(f, "Per unit"),
+        }
+    }
+}
+
+/// An enemy that might be attacked.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetCandidate {
+    pub entity: Entity,
+    pub translation: Vec3,
+    pub health: f32,
+    pub threat: f32,
+    pub is_base: bool,
+    pub layer: MovementLayer,
+    /// Hidden in the dark, ranged attacks can't target it.
+    pub is_concealed: bool,
+}
+
+impl TargetCandidate {
+    pub fn new(
+        entity: Entity,
+        transform: &Transform,
+        health: &Health,
+        attack_stats: Option<&AttackStats>,
+        layer: Option<&MovementLayer>,
+        is_base: bool,
+        is_concealed: bool,
+    ) -> Self {
+        Self {
+            entity,
+            translation: transform.translation,
+            health: health.current(),
+            threat: attack_stats.map_or(0., |stats| stats.attack_damage),
+            is_base,
+            layer: layer.copied().unwrap_or_default(),
+            is_concealed,
+        }
+    }
+
+    /// Whether an attack with the given stats can hit this candidate at all.
+    pub fn is_reachable_by(&self, stats: &AttackStats) -> bool {
+        (self.layer == MovementLayer::Ground || stats.can_target_air)
+            && !(self.is_concealed && stats.is_ranged())
+    }
+
+    /// Whether this candidate is in front of the origin and within the range.
+    pub fn is_in_range(&self, origin: Vec3, direction: f32, range: &RangeInclusive<f32>) -> bool {
+        let distance = self.translation.x - origin.x;
+
+        distance.signum() == direction && range.contains(&distance.abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: u32, x: f32, health: f32, is_base: bool) -> TargetCandidate {
+        TargetCandidate {
+            entity: Entity::from_raw(index),
+            translation: Vec3::new(x, 0., 0.),
+            health,
+            threat: 0.,
+            is_base,
+            layer: MovementLayer::Ground,
+            is_concealed: false,
+        }
+    }
+
+    #[test]
+    fn lowest_health_picks_the_weakest_enemy_in_range() {
+        let candidates = [
+            candidate(0, 10., 5., false),
+            candidate(1, 20., 2., false),
+            candidate(2, 200., 1., false),
+        ];
+
+        let target = TargetingPolicy::LowestHealth.select(Vec3::ZERO, 1., 0.0..=50., candidates);
+
+        assert_eq!(
+            target.map(|target| target.entity),
+            Some(Entity::from_raw(1))
+        );
+    }
+
+    #[test]
+    fn only_attacks_that_target_air_reach_flying_units() {
+        let flying = TargetCandidate {
+            layer: MovementLayer::Air,
+            ..candidate(0, 10., 5., false)
+        };
+        let ground = candidate(1, 10., 5., false);
+
+        assert!(flying.is_reachable_by(&AttackStats::from(UnitType::Archer)));
+        assert!(!flying.is_reachable_by(&AttackStats::from(UnitType::Farmer)));
+        // Flying melee units only reach units on the ground
+        assert!(!flying.is_reachable_by(&AttackStats::from(UnitType::Gryphon)));
+        assert!(!flying.is_reachable_by(&AttackStats::from(UnitType::Bat)));
+        assert!(ground.is_reachable_by(&AttackStats::from(UnitType::Farmer)));
+    }
+
+    #[test]
+    fn concealed_units_are_only_reachable_in_melee() {
+        let concealed = TargetCandidate {
+            is_concealed: true,
+            ..candidate(0, 10., 5., false)
+        };
+
+        assert!(concealed.is_reachable_by(&AttackStats::from(UnitType::Farmer)));
+        assert!(!concealed.is_reachable_by(&AttackStats::from(UnitType::Archer)));
+    }
+
+    #[test]
+    fn bases_only_ignores_units() {
+        let candidates = [candidate(0, 10., 5., false), candidate(1, 40., 100., true)];
+
+        let target = TargetingPolicy::BasesOnly.select(Vec3::ZERO, 1., 0.0..=50., candidates);
+
+        assert_eq!(
+            target.map(|target| target.entity),
+            Some(Entity::from_raw(1))
+        );
+    }
+}
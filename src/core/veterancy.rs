@@ -0,0 +1,99 @@
+//! Units gain experience from fighting and get stronger with each level.
+
+use bevy::prelude::*;
+
+use super::{
+    stats::{Health, StatModifiers},
+    CoreStep,
+};
+
+/// The experience gained for finishing off an enemy, in addition to the damage dealt.
+pub const EXPERIENCE_PER_KILL: f32 = 5.;
+
+/// The experience needed to reach each level.
+const LEVEL_THRESHOLDS: [f32; 3] = [10., 25., 50.];
+
+/// The factor by which damage and maximum health grow with each level.
+const LEVEL_UP_BOOST: f32 = 1.2;
+
+pub struct VeterancyPlugin;
+
+impl Plugin for VeterancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_veterancy.in_set(CoreStep::Modifiers))
+            .add_systems(Update, level_up.in_set(CoreStep::Effects));
+    }
+}
+
+#[derive(Debug, Component, Default)]
+pub struct Veterancy {
+    experience: f32,
+    /// The level for which the health boost has been applied.
+    level: u32,
+}
+
+impl Veterancy {
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn gain(&mut self, experience: f32) {
+        self.experience += experience;
+    }
+
+    /// The factor applied to the attack damage at the current level.
+    fn damage_boost(&self) -> f32 {
+        LEVEL_UP_BOOST.powi(self.level as i32)
+    }
+
+    /// The level reached with the current experience.
+    fn earned_level(&self) -> u32 {
+        LEVEL_THRESHOLDS
+            .iter()
+            .take_while(|threshold| self.experience >= **threshold)
+            .count() as u32
+    }
+}
+
+fn apply_veterancy(mut unit_query: Query<(&mut StatModifiers, &Veterancy)>) {
+    for (mut modifiers, veterancy) in unit_query.iter_mut() {
+        modifiers.attack_damage *= veterancy.damage_boost();
+    }
+}
+
+fn level_up(mut unit_query: Query<(&mut Veterancy, &mut Health), Changed<Veterancy>>) {
+    for (mut veterancy, mut health) in unit_query.iter_mut() {
+        while veterancy.level < veterancy.earned_level() {
+            veterancy.level += 1;
+
+            let max = health.max();
+            health.increase_max(max * (LEVEL_UP_BOOST - 1.));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earned_level_follows_thresholds() {
+        let mut veterancy = Veterancy::default();
+        assert_eq!(veterancy.earned_level(), 0);
+
+        veterancy.gain(12.);
+        assert_eq!(veterancy.earned_level(), 1);
+
+        veterancy.gain(100.);
+        assert_eq!(veterancy.earned_level(), 3);
+    }
+
+    #[test]
+    fn damage_boost_compounds_with_each_level() {
+        let mut veterancy = Veterancy::default();
+        assert_eq!(veterancy.damage_boost(), 1.);
+
+        veterancy.level = 2;
+        assert_eq!(veterancy.damage_boost(), LEVEL_UP_BOOST * LEVEL_UP_BOOST);
+    }
+}
@@ -0,0 +1,168 @@
+//! The waves of enemies sent against the player.
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+
+use std::time::Duration;
+
+use super::{spawn_unit, CoreStep, Faction, SpawnUnit, UnitType};
+
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            generate_waves
+                .run_if(on_timer(Duration::from_secs(5)))
+                .in_set(CoreStep::Prepare)
+                // Spawn the wave in the same frame
+                .before(spawn_unit),
+        );
+    }
+}
+
+/// A group of enemies that is sent at once.
+#[derive(Debug, Clone)]
+pub struct Wave {
+    pub units: Vec<(UnitType, u32)>,
+}
+
+impl Wave {
+    pub fn new(units: &[(UnitType, u32)]) -> Self {
+        Self {
+            units: units.to_vec(),
+        }
+    }
+}
+
+/// The waves sent by the enemy, in order.
+///
+/// After the last wave, the waves starting at `loop_start` are repeated.
+#[derive(Debug, Resource)]
+pub struct WaveScript {
+    waves: Vec<Wave>,
+    loop_start: usize,
+    /// The number of waves sent so far.
+    count: usize,
+}
+
+impl WaveScript {
+    pub fn new(waves: Vec<Wave>, loop_start: usize) -> Self {
+        Self {
+            waves,
+            loop_start,
+            count: 0,
+        }
+    }
+
+    /// Advance to the next wave and return it.
+    pub fn next_wave(&mut self) -> Option<&Wave> {
+        let index = if self.count < self.waves.len() {
+            self.count
+        } else {
+            let loop_length = self.waves.len().checked_sub(self.loop_start)?.max(1);
+            self.loop_start + (self.count - self.waves.len()) % loop_length
+        };
+
+        self.count += 1;
+        self.waves.get(index)
+    }
+}
+
+impl Default for WaveScript {
+    fn default() -> Self {
+        use UnitType::*;
+
+        Self::new(
+            vec![
+                Wave::new(&[(Shadow, 1)]),
+                Wave::new(&[(Shadow, 1)]),
+                Wave::new(&[(Shadow, 1), (Ghoul, 1)]),
+                Wave::new(&[(Shadow, 2)]),
+                Wave::new(&[(Ghoul, 2)]),
+                Wave::new(&[(Shadow, 1), (Bat, 1)]),
+                Wave::new(&[(Golem, 1)]),
+                Wave::new(&[(Shadow, 1), (Wraith, 1)]),
+                Wave::new(&[(Ghoul, 2), (Bat, 1)]),
+                Wave::new(&[(Splitter, 1)]),
+                Wave::new(&[(Golem, 1), (Wraith, 1)]),
+                Wave::new(&[(Shadow, 2), (Ram, 1), (Bat, 1)]),
+                // The boss comes back with every loop, until it's defeated often enough to win
+                Wave::new(&[(NightLord, 1), (Shadow, 2)]),
+                Wave::new(&[(Ghoul, 3)]),
+                Wave::new(&[(Splitter, 1), (Wraith, 1)]),
+                Wave::new(&[(Golem, 2), (Bat, 1)]),
+                Wave::new(&[(Shadow, 2), (Ram, 1), (Wraith, 1)]),
+            ],
+            12,
+        )
+    }
+}
+
+fn generate_waves(
+    mut wave_script: ResMut<WaveScript>,
+    mut spawn_unit_event: EventWriter<SpawnUnit>,
+) {
+    let Some(wave) = wave_script.next_wave() else {
+        return;
+    };
+
+    for &(unit_type, count) in wave.units.iter() {
+        for _ in 0..count {
+            spawn_unit_event.send(SpawnUnit {
+                faction: Faction::Foe,
+                unit_type,
+                x: None,
+                lane: None,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::boss::BOSSES_TO_WIN;
+
+    use super::*;
+
+    #[test]
+    fn the_boss_returns_until_it_can_be_defeated_often_enough_to_win() {
+        let mut script = WaveScript::default();
+
+        let bosses: u32 = (0..30)
+            .filter_map(|_| script.next_wave().cloned())
+            .flat_map(|wave| wave.units)
+            .filter(|(unit_type, _)| matches!(unit_type, UnitType::NightLord))
+            .map(|(_, count)| count)
+            .sum();
+
+        assert!(bosses >= BOSSES_TO_WIN);
+    }
+
+    #[test]
+    fn waves_repeat_from_the_loop_start() {
+        let mut script = WaveScript::new(
+            vec![
+                Wave::new(&[(UnitType::Shadow, 1)]),
+                Wave::new(&[(UnitType::Ghoul, 1)]),
+                Wave::new(&[(UnitType::Golem, 1)]),
+            ],
+            1,
+        );
+
+        let units: Vec<UnitType> = (0..5)
+            .map(|_| script.next_wave().unwrap().units[0].0)
+            .collect();
+
+        assert!(matches!(
+            units.as_slice(),
+            [
+                UnitType::Shadow,
+                UnitType::Ghoul,
+                UnitType::Golem,
+                UnitType::Ghoul,
+                UnitType::Golem
+            ]
+        ));
+    }
+}
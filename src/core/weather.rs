@@ -0,0 +1,134 @@
+//! The weather during a battle, changing how ranged units fight.
+
+use bevy::prelude::*;
+use bevy_turborand::prelude::*;
+
+use std::fmt::Display;
+
+use super::{
+    stats::{AttackStats, StatModifiers},
+    CoreStep, Faction, Unit,
+};
+
+/// The factor applied to the attack range of ranged units in the rain.
+const RAIN_ATTACK_RANGE: f32 = 0.8;
+
+/// How far units can see through the fog.
+pub const FOG_VISIBILITY: f32 = 70.;
+
+/// How much the wind changes the attack range of ranged units at most.
+const WIND_STRENGTH: f32 = 0.2;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_weather.in_set(CoreStep::Modifiers));
+    }
+}
+
+#[derive(Debug, Resource, Clone, Copy, PartialEq, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    /// Shortens the range of ranged units.
+    Rain,
+    /// Enemies far from the player's units are hidden, and ranged units can't shoot
+    /// further than they can see.
+    Fog,
+    /// Carries arrows further in the given direction, and shortens them against it.
+    ///
+    /// The direction is positive when the wind blows towards the enemy base.
+    Wind(f32),
+}
+
+impl Weather {
+    /// Choose a weather at random.
+    pub fn random(global_rng: &mut GlobalRng) -> Self {
+        match global_rng.usize(0..4) {
+            0 => Self::Clear,
+            1 => Self::Rain,
+            2 => Self::Fog,
+            _ => Self::Wind(if global_rng.bool() { 1. } else { -1. }),
+        }
+    }
+
+    /// How far the player can see around their light sources.
+    pub fn visibility(&self) -> f32 {
+        match *self {
+            Self::Fog => FOG_VISIBILITY,
+            _ => f32::INFINITY,
+        }
+    }
+
+    /// The factor applied to the attack range of a unit of the given faction.
+    fn attack_range_factor(&self, attack_stats: &AttackStats, faction: Faction) -> f32 {
+        if !attack_stats.is_ranged() {
+            return 1.;
+        }
+
+        match *self {
+            Self::Clear => 1.,
+            Self::Rain => RAIN_ATTACK_RANGE,
+            Self::Fog => (self.visibility() / attack_stats.attack_range).min(1.),
+            Self::Wind(direction) => 1. + WIND_STRENGTH * direction * faction.direction(),
+        }
+    }
+}
+
+impl Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::Clear => write!(f, "Clear"),
+            Self::Rain => write!(f, "Rain"),
+            Self::Fog => write!(f, "Fog"),
+            Self::Wind(direction) if direction >= 0. => write!(f, "Tailwind"),
+            Self::Wind(_) => write!(f, "Headwind"),
+        }
+    }
+}
+
+fn apply_weather(
+    weather: Res<Weather>,
+    mut unit_query: Query<(&AttackStats, &mut StatModifiers, &Faction), With<Unit>>,
+) {
+    for (attack_stats, mut modifiers, faction) in unit_query.iter_mut() {
+        modifiers.attack_range *= weather.attack_range_factor(attack_stats, *faction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::UnitType;
+
+    use super::*;
+
+    #[test]
+    fn weather_only_changes_the_range_of_ranged_units() {
+        let melee = AttackStats::from(UnitType::Farmer);
+
+        for weather in [Weather::Rain, Weather::Fog, Weather::Wind(1.)] {
+            assert_eq!(weather.attack_range_factor(&melee, Faction::Player), 1.);
+        }
+    }
+
+    #[test]
+    fn fog_limits_the_range_to_the_visibility() {
+        let archer = AttackStats::from(UnitType::Archer);
+        let factor = Weather::Fog.attack_range_factor(&archer, Faction::Player);
+
+        assert_eq!(archer.attack_range * factor, FOG_VISIBILITY);
+        assert_eq!(Weather::Fog.visibility(), FOG_VISIBILITY);
+        assert_eq!(Weather::Clear.visibility(), f32::INFINITY);
+    }
+
+    #[test]
+    fn wind_helps_the_side_it_blows_towards() {
+        let archer = AttackStats::from(UnitType::Archer);
+        let tailwind = Weather::Wind(1.);
+
+        assert!(tailwind.attack_range_factor(&archer, Faction::Player) > 1.);
+        assert!(tailwind.attack_range_factor(&archer, Faction::Foe) < 1.);
+        assert_eq!(tailwind.attack_range_factor(&archer, Faction::Neutral), 1.);
+    }
+}
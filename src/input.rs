@@ -1,9 +1,19 @@
 //! Process user input and trigger game logic events with it.
 
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 
 use crate::core::{
-    game_state::GameState, inventory::Inventory, CoreSystemSet, SpawnUnit, UnitType,
+    base_upgrades::{BaseUpgrade, UpgradeBase},
+    game_state::GameState,
+    hero::HeroCommand,
+    inventory::Inventory,
+    level::Level,
+    light::BuildTorch,
+    recall::{Recall, SelectedUnit},
+    spells::{CastSpell, Spell, SpellTarget},
+    stance::{ArmyStance, Charge, RallyPoint},
+    targeting::PlayerTargeting,
+    CoreSystemSet, Faction, SpawnUnit, Unit, UnitType,
 };
 
 pub struct InputPlugin;
@@ -16,7 +26,22 @@ impl Plugin for InputPlugin {
                 .before(CoreSystemSet)
                 .run_if(in_state(GameState::InGame)),
         )
-        .add_systems(Update, handle_input.in_set(InputSystemSet));
+        .init_resource::<AimedSpell>()
+        .add_systems(
+            Update,
+            (
+                handle_input,
+                toggle_targeting,
+                control_hero,
+                (select_spell, select_unit, aim_spell).chain(),
+                recall,
+                upgrade_base,
+                build_torch,
+                set_stance,
+                (set_rally_point, charge),
+            )
+                .in_set(InputSystemSet),
+        );
     }
 }
 
@@ -44,27 +69,416 @@ impl InputData {
                 key: KeyCode::KeyR,
                 glyph: "R".to_string(),
             }),
+            4 => Some(Self {
+                key: KeyCode::KeyT,
+                glyph: "T".to_string(),
+            }),
+            5 => Some(Self {
+                key: KeyCode::KeyY,
+                glyph: "Y".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key to use the hero ability with the given index.
+    pub fn hero_ability(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self {
+                key: KeyCode::Digit1,
+                glyph: "1".to_string(),
+            }),
+            1 => Some(Self {
+                key: KeyCode::Digit2,
+                glyph: "2".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key to cast the spell with the given index.
+    pub fn spell(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self {
+                key: KeyCode::KeyZ,
+                glyph: "Z".to_string(),
+            }),
+            1 => Some(Self {
+                key: KeyCode::KeyX,
+                glyph: "X".to_string(),
+            }),
+            2 => Some(Self {
+                key: KeyCode::KeyC,
+                glyph: "C".to_string(),
+            }),
+            3 => Some(Self {
+                key: KeyCode::KeyV,
+                glyph: "V".to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The key to order the army to advance.
+    pub fn advance() -> Self {
+        Self {
+            key: KeyCode::KeyA,
+            glyph: "A".to_string(),
+        }
+    }
+
+    /// The key to order the army to hold the line at the mouse cursor.
+    pub fn hold() -> Self {
+        Self {
+            key: KeyCode::KeyS,
+            glyph: "S".to_string(),
+        }
+    }
+
+    /// The key to order the army to retreat to the base.
+    pub fn retreat() -> Self {
+        Self {
+            key: KeyCode::KeyD,
+            glyph: "D".to_string(),
+        }
+    }
+
+    /// The key to send the units waiting at the rally point into battle.
+    pub fn charge() -> Self {
+        Self {
+            key: KeyCode::Space,
+            glyph: "Space".to_string(),
+        }
+    }
+
+    /// The key to buy the base upgrade with the given index.
+    pub fn base_upgrade(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self {
+                key: KeyCode::KeyF,
+                glyph: "F".to_string(),
+            }),
+            1 => Some(Self {
+                key: KeyCode::KeyG,
+                glyph: "G".to_string(),
+            }),
+            2 => Some(Self {
+                key: KeyCode::KeyH,
+                glyph: "H".to_string(),
+            }),
             _ => None,
         }
     }
+
+    /// The key to send the selected unit back to the base.
+    pub fn recall() -> Self {
+        Self {
+            key: KeyCode::KeyB,
+            glyph: "B".to_string(),
+        }
+    }
+
+    /// The modifier key to spawn new units in the top lane.
+    pub fn top_lane() -> Self {
+        Self {
+            key: KeyCode::ShiftLeft,
+            glyph: "Shift".to_string(),
+        }
+    }
+
+    /// The modifier key to spawn new units in the bottom lane.
+    pub fn bottom_lane() -> Self {
+        Self {
+            key: KeyCode::ControlLeft,
+            glyph: "Ctrl".to_string(),
+        }
+    }
+
+    /// The key to build a torch at the cursor.
+    pub fn torch() -> Self {
+        Self {
+            key: KeyCode::KeyL,
+            glyph: "L".to_string(),
+        }
+    }
+
+    /// The key to switch the targeting policy of all player units.
+    pub fn targeting() -> Self {
+        Self {
+            key: KeyCode::Tab,
+            glyph: "Tab".to_string(),
+        }
+    }
 }
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 struct InputSystemSet;
 
+/// The spell that is cast at the next click on the battlefield.
+#[derive(Debug, Resource, Default)]
+pub struct AimedSpell(pub Option<Spell>);
+
+/// How far from a unit a click still selects it.
+const SELECTION_RADIUS: f32 = 20.;
+
+/// The position of the mouse cursor in the game world.
+fn cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let window = window_query.get_single().ok()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+
+    camera.viewport_to_world_2d(camera_transform, window.cursor_position()?)
+}
+
+/// The lane picked for new units, with a modifier key or else by the mouse cursor.
+fn picked_lane(
+    keyboard_input: &ButtonInput<KeyCode>,
+    cursor_position: Option<Vec2>,
+    level: &Level,
+) -> Option<usize> {
+    if keyboard_input.pressed(InputData::top_lane().key) {
+        Some(0)
+    } else if keyboard_input.pressed(InputData::bottom_lane().key) {
+        Some(level.lanes.len().saturating_sub(1))
+    } else {
+        cursor_position.map(|position| level.nearest_lane(position.y))
+    }
+}
+
 fn handle_input(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    level: Res<Level>,
     mut inventory: ResMut<Inventory>,
     mut spawn_unit_event: EventWriter<SpawnUnit>,
 ) {
     for (index, &unit_type) in UnitType::player_units().iter().enumerate() {
-        let key = InputData::from_slot(index).unwrap().key;
+        // Only the first units have a key to spawn them
+        let Some(InputData { key, .. }) = InputData::from_slot(index) else {
+            continue;
+        };
 
         if keyboard_input.just_released(key) && inventory.coins.try_remove(unit_type.cost()) {
+            let cursor_position = cursor_position(&window_query, &camera_query);
+
             spawn_unit_event.send(SpawnUnit {
-                is_foe: false,
+                faction: Faction::Player,
                 unit_type,
+                x: None,
+                lane: picked_lane(&keyboard_input, cursor_position, &level),
             });
         }
     }
 }
+
+fn toggle_targeting(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut player_targeting: ResMut<PlayerTargeting>,
+) {
+    if keyboard_input.just_released(InputData::targeting().key) {
+        player_targeting.cycle();
+    }
+}
+
+fn control_hero(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut hero_command: EventWriter<HeroCommand>,
+) {
+    let mut direction = 0.;
+
+    if keyboard_input.pressed(KeyCode::ArrowLeft) {
+        direction -= 1.;
+    }
+
+    if keyboard_input.pressed(KeyCode::ArrowRight) {
+        direction += 1.;
+    }
+
+    if direction != 0. {
+        hero_command.send(HeroCommand::Move(direction));
+    }
+
+    for index in 0.. {
+        let Some(input) = InputData::hero_ability(index) else {
+            break;
+        };
+
+        if keyboard_input.just_pressed(input.key) {
+            hero_command.send(HeroCommand::UseAbility(index));
+        }
+    }
+}
+
+fn select_spell(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut aimed_spell: ResMut<AimedSpell>,
+    mut cast_spell_event: EventWriter<CastSpell>,
+) {
+    for (index, spell) in Spell::all().into_iter().enumerate() {
+        let Some(InputData { key, .. }) = InputData::spell(index) else {
+            continue;
+        };
+
+        if !keyboard_input.just_released(key) {
+            continue;
+        }
+
+        if !spell.is_aimed() {
+            cast_spell_event.send(CastSpell {
+                spell,
+                target: None,
+            });
+        } else if aimed_spell.0 == Some(spell) {
+            // Pressing the key again cancels aiming
+            aimed_spell.0 = None;
+        } else {
+            aimed_spell.0 = Some(spell);
+        }
+    }
+}
+
+fn aim_spell(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut aimed_spell: ResMut<AimedSpell>,
+    mut cast_spell_event: EventWriter<CastSpell>,
+    level: Res<Level>,
+) {
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+
+    let Some(spell) = aimed_spell.0 else {
+        return;
+    };
+
+    let Some(position) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    aimed_spell.0 = None;
+
+    cast_spell_event.send(CastSpell {
+        spell,
+        target: Some(SpellTarget {
+            x: position.x,
+            lane: level.nearest_lane(position.y),
+        }),
+    });
+}
+
+fn set_stance(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut stance: ResMut<ArmyStance>,
+) {
+    if keyboard_input.just_released(InputData::advance().key) {
+        *stance = ArmyStance::Advance;
+    }
+
+    if keyboard_input.just_released(InputData::hold().key) {
+        if let Some(position) = cursor_position(&window_query, &camera_query) {
+            *stance = ArmyStance::Hold(position.x);
+        }
+    }
+
+    if keyboard_input.just_released(InputData::retreat().key) {
+        *stance = ArmyStance::Retreat;
+    }
+}
+
+fn set_rally_point(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut rally_point: ResMut<RallyPoint>,
+) {
+    if !mouse_input.just_released(MouseButton::Right) {
+        return;
+    }
+
+    if let Some(position) = cursor_position(&window_query, &camera_query) {
+        rally_point.0 = Some(position.x);
+    }
+}
+
+fn charge(keyboard_input: Res<ButtonInput<KeyCode>>, mut charge_event: EventWriter<Charge>) {
+    if keyboard_input.just_released(InputData::charge().key) {
+        charge_event.send(Charge);
+    }
+}
+
+/// Select the player unit closest to the click, unless a spell is being aimed.
+fn select_unit(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    aimed_spell: Res<AimedSpell>,
+    mut selected_unit: ResMut<SelectedUnit>,
+    unit_query: Query<(Entity, &Transform, &Faction), With<Unit>>,
+) {
+    if !mouse_input.just_released(MouseButton::Left) || aimed_spell.0.is_some() {
+        return;
+    }
+
+    let Some(position) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    selected_unit.0 = unit_query
+        .iter()
+        .filter(|(_, _, faction)| **faction == Faction::Player)
+        .map(|(unit, transform, _)| (unit, transform.translation.truncate().distance(position)))
+        .filter(|(_, distance)| *distance <= SELECTION_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(unit, _)| unit);
+}
+
+fn recall(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut selected_unit: ResMut<SelectedUnit>,
+    mut recall_event: EventWriter<Recall>,
+) {
+    if !keyboard_input.just_released(InputData::recall().key) {
+        return;
+    }
+
+    if let Some(unit) = selected_unit.0.take() {
+        recall_event.send(Recall { unit });
+    }
+}
+
+fn upgrade_base(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut upgrade_base_event: EventWriter<UpgradeBase>,
+) {
+    for (index, upgrade) in BaseUpgrade::all().into_iter().enumerate() {
+        let Some(InputData { key, .. }) = InputData::base_upgrade(index) else {
+            continue;
+        };
+
+        if keyboard_input.just_released(key) {
+            upgrade_base_event.send(UpgradeBase(upgrade));
+        }
+    }
+}
+
+fn build_torch(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut build_torch_event: EventWriter<BuildTorch>,
+) {
+    if !keyboard_input.just_released(InputData::torch().key) {
+        return;
+    }
+
+    if let Some(position) = cursor_position(&window_query, &camera_query) {
+        build_torch_event.send(BuildTorch { x: position.x });
+    }
+}
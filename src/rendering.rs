@@ -1,12 +1,36 @@
 //! Display the game on the screen.
 
 use bevy::{
-    color::palettes::css::GRAY,
+    color::palettes::css::{GOLD, GRAY, LIME, ORANGE, ROYAL_BLUE, SADDLE_BROWN, YELLOW},
     prelude::*,
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 
-use crate::core::{game_state::GameState, Base, CoreSystemSet, Foe, Unit};
+use crate::core::{
+    boss::Boss,
+    capture::{CapturePoint, CAPTURE_RADIUS},
+    game_state::GameState,
+    hero::Hero,
+    level::{Level, Terrain},
+    light::{Concealed, Torch},
+    recall::SelectedUnit,
+    stance::RallyPoint,
+    stats::{Health, MovementLayer},
+    veterancy::Veterancy,
+    Base, CoreSystemSet, Faction, Unit,
+};
+
+/// How high flying units are drawn above the ground.
+const AIR_HEIGHT: f32 = 40.;
+
+/// How much larger bosses are drawn than other units.
+const BOSS_SCALE: f32 = 2.;
+
+/// How high the rank pips of veteran units are drawn above the unit.
+const RANK_HEIGHT: f32 = 25.;
+
+/// The horizontal distance between two rank pips.
+const RANK_SPACING: f32 = 8.;
 
 pub struct RenderingPlugin;
 
@@ -24,11 +48,33 @@ impl Plugin for RenderingPlugin {
         )
         .add_systems(
             OnEnter(GameState::InGame),
-            (setup_in_game, setup_base_graphics)
+            (
+                setup_in_game,
+                (
+                    setup_base_graphics,
+                    setup_terrain_graphics,
+                    setup_rally_marker,
+                    setup_selection_marker,
+                ),
+            )
                 .chain()
                 .in_set(RenderingSystemSet),
         )
-        .add_systems(Update, spawn_unit_graphics.in_set(RenderingSystemSet));
+        .add_systems(
+            Update,
+            (
+                spawn_unit_graphics,
+                update_rank_graphics,
+                update_rally_marker,
+                update_selection_marker,
+                update_base_damage,
+                spawn_torch_graphics,
+                spawn_capture_point_graphics,
+                update_capture_flags,
+                update_concealment,
+            )
+                .in_set(RenderingSystemSet),
+        );
     }
 }
 
@@ -39,15 +85,53 @@ struct RenderingSystemSet;
 struct CustomMeshes {
     unit: Mesh2dHandle,
     base: Mesh2dHandle,
+    rank_pip: Mesh2dHandle,
+    rally_marker: Mesh2dHandle,
+    selection_marker: Mesh2dHandle,
+    torch: Mesh2dHandle,
+    capture_area: Mesh2dHandle,
+    capture_flag: Mesh2dHandle,
 }
 
 #[derive(Debug, Resource)]
 struct CustomMaterials {
     friend_unit: Handle<ColorMaterial>,
     foe_unit: Handle<ColorMaterial>,
+    neutral_unit: Handle<ColorMaterial>,
+    hero: Handle<ColorMaterial>,
     base: Handle<ColorMaterial>,
+    base_damaged: Handle<ColorMaterial>,
+    base_ruined: Handle<ColorMaterial>,
+    rank_pip: Handle<ColorMaterial>,
+    rally_marker: Handle<ColorMaterial>,
+    selection_marker: Handle<ColorMaterial>,
+    torch: Handle<ColorMaterial>,
+    capture_area: Handle<ColorMaterial>,
+    neutral_flag: Handle<ColorMaterial>,
 }
 
+/// How high the flag of a capture point is drawn, above all lanes.
+const CAPTURE_FLAG_HEIGHT: f32 = 110.;
+
+/// How far below the units the ground is drawn.
+const GROUND_HEIGHT: f32 = -24.;
+
+/// The mesh of a base, showing how damaged it is.
+#[derive(Debug, Component)]
+struct BaseMesh;
+
+/// Shows where the rally point is.
+#[derive(Debug, Component)]
+struct RallyMarker;
+
+/// Shows which unit is selected.
+#[derive(Debug, Component)]
+struct SelectionMarker;
+
+/// Marks one level of a veteran unit.
+#[derive(Debug, Component)]
+struct RankPip;
+
 fn setup_in_game(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -58,11 +142,27 @@ fn setup_in_game(
     let custom_meshes = CustomMeshes {
         unit: Mesh2dHandle(meshes.add(Capsule2d::new(10.0, 20.0))),
         base: Mesh2dHandle(meshes.add(Rectangle::new(100.0, 150.0))),
+        rank_pip: Mesh2dHandle(meshes.add(Circle::new(3.0))),
+        rally_marker: Mesh2dHandle(meshes.add(Rectangle::new(2.0, 200.0))),
+        selection_marker: Mesh2dHandle(meshes.add(Annulus::new(14.0, 16.0))),
+        torch: Mesh2dHandle(meshes.add(Rectangle::new(4.0, 20.0))),
+        capture_area: Mesh2dHandle(meshes.add(Rectangle::new(CAPTURE_RADIUS * 2., 200.0))),
+        capture_flag: Mesh2dHandle(meshes.add(Rectangle::new(20.0, 12.0))),
     };
     let custom_materials = CustomMaterials {
         friend_unit: materials.add(Color::WHITE),
         foe_unit: materials.add(Color::BLACK),
+        neutral_unit: materials.add(Color::from(SADDLE_BROWN)),
+        hero: materials.add(Color::from(ROYAL_BLUE)),
         base: materials.add(Color::from(GRAY)),
+        base_damaged: materials.add(Color::srgb(0.35, 0.3, 0.3)),
+        base_ruined: materials.add(Color::srgb(0.3, 0.15, 0.1)),
+        rank_pip: materials.add(Color::from(GOLD)),
+        rally_marker: materials.add(Color::from(LIME)),
+        selection_marker: materials.add(Color::from(YELLOW)),
+        torch: materials.add(Color::from(ORANGE)),
+        capture_area: materials.add(Color::srgba(1., 1., 1., 0.05)),
+        neutral_flag: materials.add(Color::srgb(0.6, 0.6, 0.6)),
     };
 
     commands.insert_resource(custom_meshes);
@@ -77,33 +177,315 @@ fn setup_base_graphics(
 ) {
     for spawned_base in spawned_base_query.iter() {
         commands.entity(spawned_base).with_children(|parent| {
+            parent.spawn((
+                BaseMesh,
+                MaterialMesh2dBundle {
+                    mesh: meshes.base.clone(),
+                    material: materials.base.clone(),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+fn setup_terrain_graphics(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    level: Res<Level>,
+) {
+    for (index, segment) in level.terrain.iter().enumerate() {
+        let color = match segment.terrain {
+            Terrain::Hill => Color::srgb(0.35, 0.5, 0.25),
+            Terrain::River => Color::srgb(0.2, 0.35, 0.7),
+            Terrain::Bridge => Color::srgb(0.45, 0.3, 0.15),
+        };
+
+        let mesh = Mesh2dHandle(meshes.add(Rectangle::new(segment.end - segment.start, 8.0)));
+        let material = materials.add(color);
+
+        // The terrain stretches across all lanes
+        for lane_y in level.lanes.iter() {
+            commands.spawn(MaterialMesh2dBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(
+                    (segment.start + segment.end) / 2.,
+                    lane_y + GROUND_HEIGHT,
+                    // Earlier segments lie on top, like a bridge over a river
+                    -20. - index as f32 * 0.1,
+                ),
+                ..default()
+            });
+        }
+    }
+}
+
+/// Change the look of the bases as they lose health.
+fn update_base_damage(
+    materials: Res<CustomMaterials>,
+    base_query: Query<(&Health, &Children), (With<Base>, Changed<Health>)>,
+    mut mesh_query: Query<&mut Handle<ColorMaterial>, With<BaseMesh>>,
+) {
+    for (health, children) in base_query.iter() {
+        let health_fraction = health.current() / health.max();
+
+        let material = if health_fraction > 0.66 {
+            &materials.base
+        } else if health_fraction > 0.33 {
+            &materials.base_damaged
+        } else {
+            &materials.base_ruined
+        };
+
+        for child in children.iter() {
+            if let Ok(mut mesh_material) = mesh_query.get_mut(*child) {
+                *mesh_material = material.clone();
+            }
+        }
+    }
+}
+
+fn spawn_torch_graphics(
+    mut commands: Commands,
+    meshes: Res<CustomMeshes>,
+    materials: Res<CustomMaterials>,
+    spawned_torch_query: Query<Entity, Added<Torch>>,
+) {
+    for spawned_torch in spawned_torch_query.iter() {
+        commands.entity(spawned_torch).with_children(|parent| {
             parent.spawn(MaterialMesh2dBundle {
-                mesh: meshes.base.clone(),
-                material: materials.base.clone(),
+                mesh: meshes.torch.clone(),
+                material: materials.torch.clone(),
                 ..default()
             });
         });
     }
 }
 
+/// The flag above a capture point, showing who holds it.
+#[derive(Debug, Component)]
+struct CaptureFlag;
+
+fn spawn_capture_point_graphics(
+    mut commands: Commands,
+    meshes: Res<CustomMeshes>,
+    materials: Res<CustomMaterials>,
+    spawned_point_query: Query<Entity, Added<CapturePoint>>,
+) {
+    for spawned_point in spawned_point_query.iter() {
+        commands.entity(spawned_point).with_children(|parent| {
+            parent.spawn(MaterialMesh2dBundle {
+                mesh: meshes.capture_area.clone(),
+                material: materials.capture_area.clone(),
+                ..default()
+            });
+
+            parent.spawn((
+                CaptureFlag,
+                MaterialMesh2dBundle {
+                    mesh: meshes.capture_flag.clone(),
+                    material: materials.neutral_flag.clone(),
+                    transform: Transform::from_xyz(0., CAPTURE_FLAG_HEIGHT, 1.),
+                    ..default()
+                },
+            ));
+        });
+    }
+}
+
+fn update_capture_flags(
+    materials: Res<CustomMaterials>,
+    point_query: Query<(&CapturePoint, &Children), Changed<CapturePoint>>,
+    mut flag_query: Query<&mut Handle<ColorMaterial>, With<CaptureFlag>>,
+) {
+    for (point, children) in point_query.iter() {
+        let material = match point.owner {
+            Some(Faction::Foe) => &materials.foe_unit,
+            Some(Faction::Player) => &materials.friend_unit,
+            Some(Faction::Neutral) | None => &materials.neutral_flag,
+        };
+
+        for child in children.iter() {
+            if let Ok(mut flag_material) = flag_query.get_mut(*child) {
+                flag_material.set_if_neq(material.clone());
+            }
+        }
+    }
+}
+
+/// Hide the enemy and neutral units that are in the dark.
+fn update_concealment(mut unit_query: Query<(&mut Visibility, Has<Concealed>), With<Unit>>) {
+    for (mut visibility, is_concealed) in unit_query.iter_mut() {
+        let target = if is_concealed {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+
+        visibility.set_if_neq(target);
+    }
+}
+
+fn setup_rally_marker(
+    mut commands: Commands,
+    meshes: Res<CustomMeshes>,
+    materials: Res<CustomMaterials>,
+) {
+    commands.spawn((
+        RallyMarker,
+        MaterialMesh2dBundle {
+            mesh: meshes.rally_marker.clone(),
+            material: materials.rally_marker.clone(),
+            transform: Transform::from_xyz(0., 0., -5.),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+fn update_rally_marker(
+    rally_point: Res<RallyPoint>,
+    mut marker_query: Query<(&mut Transform, &mut Visibility), With<RallyMarker>>,
+) {
+    if !rally_point.is_changed() {
+        return;
+    }
+
+    for (mut transform, mut visibility) in marker_query.iter_mut() {
+        match rally_point.0 {
+            Some(x) => {
+                transform.translation.x = x;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn setup_selection_marker(
+    mut commands: Commands,
+    meshes: Res<CustomMeshes>,
+    materials: Res<CustomMaterials>,
+) {
+    commands.spawn((
+        SelectionMarker,
+        MaterialMesh2dBundle {
+            mesh: meshes.selection_marker.clone(),
+            material: materials.selection_marker.clone(),
+            transform: Transform::from_xyz(0., 0., 30.),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+fn update_selection_marker(
+    selected_unit: Res<SelectedUnit>,
+    unit_query: Query<&Transform, (With<Unit>, Without<SelectionMarker>)>,
+    mut marker_query: Query<(&mut Transform, &mut Visibility), With<SelectionMarker>>,
+) {
+    let selected_transform = selected_unit.0.and_then(|unit| unit_query.get(unit).ok());
+
+    for (mut transform, mut visibility) in marker_query.iter_mut() {
+        match selected_transform {
+            Some(selected_transform) => {
+                transform.translation.x = selected_transform.translation.x;
+                transform.translation.y = selected_transform.translation.y;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
 fn spawn_unit_graphics(
     mut commands: Commands,
     meshes: Res<CustomMeshes>,
     materials: Res<CustomMaterials>,
-    spawned_unit_query: Query<(Entity, Has<Foe>), Added<Unit>>,
+    spawned_unit_query: Query<
+        (
+            Entity,
+            Option<&MovementLayer>,
+            Has<Boss>,
+            Has<Hero>,
+            &Faction,
+        ),
+        Added<Unit>,
+    >,
 ) {
-    for (spawned_unit, is_foe) in spawned_unit_query.iter() {
+    for (spawned_unit, layer, is_boss, is_hero, faction) in spawned_unit_query.iter() {
         commands.entity(spawned_unit).with_children(|parent| {
-            let material = if is_foe {
-                materials.foe_unit.clone()
-            } else {
-                materials.friend_unit.clone()
+            let material = match faction {
+                Faction::Neutral => materials.neutral_unit.clone(),
+                Faction::Foe => materials.foe_unit.clone(),
+                Faction::Player if is_hero => materials.hero.clone(),
+                Faction::Player => materials.friend_unit.clone(),
+            };
+            // Draw flying units above the others
+            let mut transform = match layer {
+                Some(MovementLayer::Air) => Transform::from_xyz(0., AIR_HEIGHT, 20.),
+                _ => Transform::default(),
             };
+
+            if is_boss {
+                transform.scale = Vec3::splat(BOSS_SCALE);
+            }
+
             parent.spawn(MaterialMesh2dBundle {
                 mesh: meshes.unit.clone(),
                 material,
+                transform,
                 ..default()
             });
         });
     }
 }
+
+fn update_rank_graphics(
+    mut commands: Commands,
+    meshes: Res<CustomMeshes>,
+    materials: Res<CustomMaterials>,
+    unit_query: Query<
+        (
+            Entity,
+            &Veterancy,
+            Option<&MovementLayer>,
+            Option<&Children>,
+        ),
+        Changed<Veterancy>,
+    >,
+    pip_query: Query<(), With<RankPip>>,
+) {
+    for (unit, veterancy, layer, children) in unit_query.iter() {
+        let pip_count = children
+            .map(|children| {
+                children
+                    .iter()
+                    .filter(|child| pip_query.contains(**child))
+                    .count()
+            })
+            .unwrap_or_default() as u32;
+
+        let height = match layer {
+            Some(MovementLayer::Air) => AIR_HEIGHT + RANK_HEIGHT,
+            _ => RANK_HEIGHT,
+        };
+
+        commands.entity(unit).with_children(|parent| {
+            // Add a pip for each level gained since the last update
+            for index in pip_count..veterancy.level() {
+                parent.spawn((
+                    RankPip,
+                    MaterialMesh2dBundle {
+                        mesh: meshes.rank_pip.clone(),
+                        material: materials.rank_pip.clone(),
+                        transform: Transform::from_xyz(index as f32 * RANK_SPACING, height, 30.),
+                        ..default()
+                    },
+                ));
+            }
+        });
+    }
+}
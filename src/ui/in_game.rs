@@ -1,8 +1,20 @@
-use bevy::prelude::*;
+use bevy::{color::palettes::css::YELLOW, prelude::*};
 
 use crate::{
-    core::{game_state::GameState, inventory::Inventory, CoreSystemSet, UnitType},
-    input::InputData,
+    core::{
+        base_upgrades::{BaseUpgrade, BaseUpgrades},
+        boss::Boss,
+        game_state::GameState,
+        inventory::Inventory,
+        light::{TimeOfDay, TORCH_COST},
+        spells::Spell,
+        stance::ArmyStance,
+        stats::Health,
+        targeting::PlayerTargeting,
+        weather::Weather,
+        CoreSystemSet, UnitType,
+    },
+    input::{AimedSpell, InputData},
 };
 
 use super::UiSystemSet;
@@ -19,7 +31,20 @@ impl Plugin for InGameUiPlugin {
                     .run_if(in_state(GameState::InGame)),
             )
             .add_systems(OnEnter(GameState::InGame), spawn.in_set(UiSystemSet))
-            .add_systems(Update, update_coins.in_set(UiSystemSet))
+            .add_systems(
+                Update,
+                (
+                    update_coins,
+                    update_mana,
+                    update_targeting,
+                    update_stance,
+                    update_weather,
+                    update_boss_bar,
+                    update_spell_slots,
+                    update_upgrade_slots,
+                )
+                    .in_set(UiSystemSet),
+            )
             .add_systems(OnExit(GameState::InGame), despawn.in_set(UiSystemSet));
     }
 }
@@ -30,6 +55,33 @@ struct InGameUi;
 #[derive(Debug, Component)]
 struct CoinText;
 
+#[derive(Debug, Component)]
+struct ManaText;
+
+#[derive(Debug, Component)]
+struct TargetingText;
+
+#[derive(Debug, Component)]
+struct StanceText;
+
+#[derive(Debug, Component)]
+struct WeatherText;
+
+#[derive(Debug, Component)]
+struct SpellSlot(Spell);
+
+#[derive(Debug, Component)]
+struct UpgradeSlot(BaseUpgrade);
+
+#[derive(Debug, Component)]
+struct BossBar;
+
+#[derive(Debug, Component)]
+struct BossNameText;
+
+#[derive(Debug, Component)]
+struct BossHealthFill;
+
 fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/fira_sans/FiraSans-Medium.ttf");
     let header_style = TextStyle {
@@ -64,6 +116,7 @@ fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
                     style: Style {
                         width: Val::Percent(100.),
                         padding: UiRect::all(Val::Px(10.)),
+                        justify_content: JustifyContent::SpaceBetween,
                         ..default()
                     },
                     ..default()
@@ -76,39 +129,201 @@ fn spawn(mut commands: Commands, asset_server: Res<AssetServer>) {
                             TextSection::new(" G", header_style.clone()),
                         ]),
                     ));
+
+                    child.spawn((
+                        ManaText,
+                        TextBundle::from_sections([
+                            TextSection::new("0", header_style.clone()),
+                            TextSection::new(" M", header_style.clone()),
+                        ]),
+                    ));
+
+                    let glyph = InputData::targeting().glyph;
+
+                    child.spawn((
+                        TargetingText,
+                        TextBundle::from_sections([
+                            TextSection::new("[", footer_style.clone()),
+                            TextSection::new(glyph, footer_style.clone()),
+                            TextSection::new("] Targeting: ", footer_style.clone()),
+                            TextSection::new("", footer_style.clone()),
+                        ]),
+                    ));
+
+                    let glyphs = format!(
+                        "{}/{}/{}",
+                        InputData::advance().glyph,
+                        InputData::hold().glyph,
+                        InputData::retreat().glyph
+                    );
+
+                    child.spawn((
+                        StanceText,
+                        TextBundle::from_sections([
+                            TextSection::new("[", footer_style.clone()),
+                            TextSection::new(glyphs, footer_style.clone()),
+                            TextSection::new("] Stance: ", footer_style.clone()),
+                            TextSection::new("", footer_style.clone()),
+                        ]),
+                    ));
+
+                    child.spawn((
+                        WeatherText,
+                        TextBundle::from_section("", footer_style.clone()),
+                    ));
                 });
 
-            // Bottom bar
+            // Boss health bar, only shown while a boss is on the battlefield
+            child
+                .spawn((
+                    BossBar,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            top: Val::Px(80.),
+                            left: Val::Percent(25.),
+                            width: Val::Percent(50.),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            display: Display::None,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|child| {
+                    child.spawn((
+                        BossNameText,
+                        TextBundle::from_section("", footer_style.clone()),
+                    ));
+
+                    child
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.),
+                                height: Val::Px(20.),
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                            ..default()
+                        })
+                        .with_children(|child| {
+                            child.spawn((
+                                BossHealthFill,
+                                NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(100.),
+                                        height: Val::Percent(100.),
+                                        ..default()
+                                    },
+                                    background_color: Color::srgb(0.7, 0.1, 0.1).into(),
+                                    ..default()
+                                },
+                            ));
+                        });
+                });
+
+            // Bottom bar, with a column each for units, spells and base upgrades
             child
                 .spawn(NodeBundle {
                     style: Style {
                         width: Val::Percent(100.),
                         padding: UiRect::all(Val::Px(10.)),
-                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::FlexEnd,
                         ..default()
                     },
                     ..default()
                 })
                 .with_children(|child| {
-                    for (index, unit_type) in UnitType::player_units().iter().enumerate() {
-                        let glyph = InputData::from_slot(index).unwrap().glyph;
-                        let unit_name = format!("{unit_type}");
-                        let cost = unit_type.cost().to_string();
+                    child.spawn(column()).with_children(|child| {
+                        for (index, unit_type) in UnitType::player_units().iter().enumerate() {
+                            let Some(InputData { glyph, .. }) = InputData::from_slot(index) else {
+                                continue;
+                            };
+                            let unit_name = format!("{unit_type}");
+                            let cost = unit_type.cost().to_string();
+
+                            child.spawn((TextBundle::from_sections([
+                                TextSection::new("[", footer_style.clone()),
+                                TextSection::new(glyph, footer_style.clone()),
+                                TextSection::new("] ", footer_style.clone()),
+                                TextSection::new(unit_name, footer_style.clone()),
+                                TextSection::new(" (", footer_style.clone()),
+                                TextSection::new(cost, footer_style.clone()),
+                                TextSection::new(" G)", footer_style.clone()),
+                            ]),));
+                        }
+                    });
+
+                    child.spawn(column()).with_children(|child| {
+                        for (index, spell) in Spell::all().into_iter().enumerate() {
+                            let Some(InputData { glyph, .. }) = InputData::spell(index) else {
+                                continue;
+                            };
+                            let spell_name = format!("{spell}");
+                            let cost = spell.cost().to_string();
+
+                            child.spawn((
+                                SpellSlot(spell),
+                                TextBundle::from_sections([
+                                    TextSection::new("[", footer_style.clone()),
+                                    TextSection::new(glyph, footer_style.clone()),
+                                    TextSection::new("] ", footer_style.clone()),
+                                    TextSection::new(spell_name, footer_style.clone()),
+                                    TextSection::new(" (", footer_style.clone()),
+                                    TextSection::new(cost, footer_style.clone()),
+                                    TextSection::new(" M)", footer_style.clone()),
+                                ]),
+                            ));
+                        }
+                    });
 
-                        child.spawn((TextBundle::from_sections([
+                    child.spawn(column()).with_children(|child| {
+                        for (index, upgrade) in BaseUpgrade::all().into_iter().enumerate() {
+                            let Some(InputData { glyph, .. }) = InputData::base_upgrade(index)
+                            else {
+                                continue;
+                            };
+                            let upgrade_name = format!("{upgrade}");
+
+                            child.spawn((
+                                UpgradeSlot(upgrade),
+                                TextBundle::from_sections([
+                                    TextSection::new("[", footer_style.clone()),
+                                    TextSection::new(glyph, footer_style.clone()),
+                                    TextSection::new("] ", footer_style.clone()),
+                                    TextSection::new(upgrade_name, footer_style.clone()),
+                                    TextSection::new("", footer_style.clone()),
+                                ]),
+                            ));
+                        }
+
+                        let glyph = InputData::torch().glyph;
+                        let cost = TORCH_COST.to_string();
+
+                        child.spawn(TextBundle::from_sections([
                             TextSection::new("[", footer_style.clone()),
                             TextSection::new(glyph, footer_style.clone()),
-                            TextSection::new("] ", footer_style.clone()),
-                            TextSection::new(unit_name, footer_style.clone()),
-                            TextSection::new(" (", footer_style.clone()),
+                            TextSection::new("] Torch (", footer_style.clone()),
                             TextSection::new(cost, footer_style.clone()),
                             TextSection::new(" G)", footer_style.clone()),
-                        ]),));
-                    }
+                        ]));
+                    });
                 });
         });
 }
 
+fn column() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        ..default()
+    }
+}
+
 fn despawn(mut commands: Commands, in_game_ui_query: Query<Entity, With<InGameUi>>) {
     for in_game_ui in in_game_ui_query.iter() {
         commands.entity(in_game_ui).despawn_recursive();
@@ -119,3 +334,85 @@ fn update_coins(mut query: Query<&mut Text, With<CoinText>>, inventory: Res<Inve
     let mut text = query.single_mut();
     text.sections[0].value = format!("{:.0}", inventory.coins);
 }
+
+fn update_mana(mut query: Query<&mut Text, With<ManaText>>, inventory: Res<Inventory>) {
+    let mut text = query.single_mut();
+    text.sections[0].value = format!("{:.0}", inventory.mana);
+}
+
+fn update_targeting(
+    mut query: Query<&mut Text, With<TargetingText>>,
+    player_targeting: Res<PlayerTargeting>,
+) {
+    let mut text = query.single_mut();
+    text.sections[3].value = format!("{}", *player_targeting);
+}
+
+fn update_stance(mut query: Query<&mut Text, With<StanceText>>, stance: Res<ArmyStance>) {
+    let mut text = query.single_mut();
+    text.sections[3].value = format!("{}", *stance);
+}
+
+fn update_weather(
+    mut query: Query<&mut Text, With<WeatherText>>,
+    weather: Res<Weather>,
+    time_of_day: Res<TimeOfDay>,
+) {
+    if weather.is_changed() || time_of_day.is_changed() {
+        query.single_mut().sections[0].value = format!("{}, {}", *weather, *time_of_day);
+    }
+}
+
+fn update_boss_bar(
+    mut bar_query: Query<&mut Style, (With<BossBar>, Without<BossHealthFill>)>,
+    mut fill_query: Query<&mut Style, (With<BossHealthFill>, Without<BossBar>)>,
+    mut name_query: Query<&mut Text, With<BossNameText>>,
+    boss_query: Query<(&UnitType, &Health), With<Boss>>,
+) {
+    let mut bar_style = bar_query.single_mut();
+
+    let Some((unit_type, health)) = boss_query.iter().next() else {
+        bar_style.display = Display::None;
+        return;
+    };
+
+    bar_style.display = Display::Flex;
+    fill_query.single_mut().width = Val::Percent(100. * health.current().max(0.) / health.max());
+    name_query.single_mut().sections[0].value = format!("{unit_type}");
+}
+
+/// Highlight the spell that is being aimed.
+fn update_spell_slots(mut query: Query<(&mut Text, &SpellSlot)>, aimed_spell: Res<AimedSpell>) {
+    if !aimed_spell.is_changed() {
+        return;
+    }
+
+    for (mut text, SpellSlot(spell)) in query.iter_mut() {
+        let color = if aimed_spell.0 == Some(*spell) {
+            Color::from(YELLOW)
+        } else {
+            Color::WHITE
+        };
+
+        for section in text.sections.iter_mut() {
+            section.style.color = color;
+        }
+    }
+}
+
+/// Show the current cost of each base upgrade, or why it's not available.
+fn update_upgrade_slots(
+    mut query: Query<(&mut Text, &UpgradeSlot)>,
+    base_upgrades: Res<BaseUpgrades>,
+) {
+    for (mut text, UpgradeSlot(upgrade)) in query.iter_mut() {
+        text.sections[4].value = match (base_upgrades.cost(*upgrade), upgrade) {
+            (Some(cost), _) => format!(" ({cost} G)"),
+            (None, BaseUpgrade::Repair) => format!(
+                " ({:.0}s)",
+                base_upgrades.repair_cooldown.remaining_secs().ceil()
+            ),
+            (None, _) => " (max)".to_string(),
+        };
+    }
+}